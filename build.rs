@@ -38,4 +38,6 @@ fn main() {
 #[cfg(target_os = "macos")]
 fn main() {
     println!("macOS: use cargo-bundle to embed icon");
-}
\ No newline at end of file
+}
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn main() {}
@@ -1,6 +1,8 @@
 use anyhow::anyhow;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
 use indexmap::IndexMap;
 use regex::Regex;
+use rusqlite::Connection;
 use std::path::PathBuf;
 
 use crate::data_io::*;
@@ -8,6 +10,30 @@ use crate::definitions::common::*;
 use crate::definitions::strings::*;
 use crate::models::data_model::*;
 use crate::models::ui_model::*;
+use crate::output_writers::*;
+use crate::reports::markdown::render_markdown_summary;
+
+/// Regex of the excluding definition, lowercase when not case-sensitive
+fn excluding_regex(excluding_def: &ExcludingDef, case_sensitive_regex: bool) -> Regex {
+    if case_sensitive_regex {
+        Regex::new(excluding_def.excluding_pattern()).unwrap()
+    } else {
+        Regex::new(excluding_def.excluding_pattern().to_lowercase().as_str()).unwrap()
+    }
+}
+
+/// Whether any kept event of a merchant matches the excluding definition
+fn is_excluded_merchant(
+    merchant: &Merchant,
+    excluding_re: &Regex,
+    case_sensitive_regex: bool,
+) -> bool {
+    merchant.events().any(|event| {
+        excluding_re.is_match(event.excluding_check_data())
+            || (!case_sensitive_regex
+                && excluding_re.is_match(event.excluding_check_data().to_lowercase().as_str()))
+    })
+}
 
 /// Build base data from app event list
 /// Base data include:
@@ -15,6 +41,9 @@ use crate::models::ui_model::*;
 /// - One-time data
 fn build_base_data(
     app_event_list: &Vec<AppEvent>,
+    start_time: Option<NaiveDateTime>,
+    end_time: Option<NaiveDateTime>,
+    opening_state: Option<&MerchantList>,
     pricing_defs: &PricingDefs,
     excluding_def: &ExcludingDef,
     case_sensitive_regex: bool,
@@ -22,13 +51,27 @@ fn build_base_data(
     let mut merchant_list: MerchantList = MerchantList::new();
     let mut total_stats: TotalStats = TotalStats::new(pricing_defs);
 
-    total_stats.set_start_time(app_event_list.first().unwrap().time().clone());
-    total_stats.set_end_time(app_event_list.last().unwrap().time().clone());
-    merchant_list.set_start_time(app_event_list.first().unwrap().time().clone());
-    merchant_list.set_end_time(app_event_list.last().unwrap().time().clone());
+    total_stats.set_start_time(start_time);
+    total_stats.set_end_time(end_time);
+    merchant_list.set_start_time(start_time);
+    merchant_list.set_end_time(end_time);
 
     total_stats.build_pretty_time_str();
 
+    //  Merchants still installed at the end of the opening state are in the active base, with or without events
+    if let Some(opening_state) = opening_state {
+        let excluding_re = excluding_regex(excluding_def, case_sensitive_regex);
+        for merchant in opening_state.merchants().values() {
+            if is_excluded_merchant(merchant, &excluding_re, case_sensitive_regex) {
+                continue;
+            }
+            let carried_merchant = merchant.carry_over(pricing_defs.one_times());
+            if *carried_merchant.active_at_start() {
+                merchant_list.update_merchant(carried_merchant);
+            }
+        }
+    }
+
     for event in app_event_list {
         // Excluding check
         let mut re = if case_sensitive_regex {
@@ -54,13 +97,16 @@ fn build_base_data(
                 .get(event.shop_domain().as_str())
                 .unwrap()
                 .clone()
+        } else if let Some(merchant) =
+            opening_state.and_then(|s| s.merchants().get(event.shop_domain().as_str()))
+        {
+            merchant.carry_over(pricing_defs.one_times())
         } else {
             Merchant::new(&event.shop_domain(), pricing_defs.one_times())
         };
 
         // Count Install, Uninstall, Store closed
-        re = Regex::new(INSTALLED_STRING).unwrap();
-        if re.is_match(event.event().as_str()) {
+        if event.is(EventCategory::Installed) {
             total_stats.increase_installed_count(1);
             current_merchant.increase_installed_count(1);
             current_merchant.push_installing_event(event);
@@ -68,8 +114,7 @@ fn build_base_data(
             continue;
         }
 
-        re = Regex::new(UNINSTALLED_STRING).unwrap();
-        if re.is_match(event.event().as_str()) {
+        if event.is(EventCategory::Uninstalled) {
             total_stats.increase_uninstalled_count(1);
             current_merchant.increase_uninstalled_count(1);
             current_merchant.push_installing_event(event);
@@ -77,26 +122,24 @@ fn build_base_data(
             continue;
         }
 
-        re = Regex::new(STORE_CLOSED_STRING).unwrap();
-        if re.is_match(event.event().as_str()) {
+        if event.is(EventCategory::StoreClosed) {
             total_stats.increase_store_closed_count(1);
             current_merchant.increase_store_closed_count(1);
-            current_merchant.push_installing_event(event);
+            current_merchant.push_store_event(event);
             merchant_list.update_merchant(current_merchant);
             continue;
         }
 
-        re = Regex::new(STORE_REOPENED_STRING).unwrap();
-        if re.is_match(event.event().as_str()) {
+        if event.is(EventCategory::StoreReopened) {
             total_stats.increase_store_reopened_count(1);
             current_merchant.increase_store_reopened_count(1);
-            current_merchant.push_installing_event(event);
+            current_merchant.push_store_event(event);
             merchant_list.update_merchant(current_merchant);
             continue;
         }
 
         //  Count One-Time
-        if ONE_TIME_ACTIVATED_STRINGS.contains(&event.event().as_str()) {
+        if event.is(EventCategory::OneTimeActivated) {
             total_stats.increase_one_time_count(1);
             current_merchant.increase_one_time_count(1);
             current_merchant.push_one_time_event(event);
@@ -127,25 +170,41 @@ fn build_base_data(
         }
 
         //  Count Subscriptions
-        if SUBSCRIPTION_ACTIVATED_STRINGS.contains(&event.event().as_str()) {
+        if event.is(EventCategory::SubscriptionActivated) {
             current_merchant.increase_subscription_activated_count(1);
             current_merchant.push_subscription_event(event);
             merchant_list.update_merchant(current_merchant);
             continue;
         }
-        if SUBSCRIPTION_CANCELED_STRINGS.contains(&event.event().as_str()) {
+        if event.is(EventCategory::SubscriptionCanceled) {
             current_merchant.increase_subscription_canceled_count(1);
             current_merchant.push_subscription_event(event);
             merchant_list.update_merchant(current_merchant);
             continue;
         }
+        if event.is(EventCategory::SubscriptionFrozen) {
+            total_stats.increase_frozen_sub_count(1);
+            current_merchant.increase_subscription_frozen_count(1);
+            current_merchant.push_subscription_event(event);
+            merchant_list.update_merchant(current_merchant);
+            continue;
+        }
+        if event.is(EventCategory::SubscriptionUnfrozen) {
+            total_stats.increase_unfrozen_sub_count(1);
+            current_merchant.increase_subscription_unfrozen_count(1);
+            current_merchant.push_subscription_event(event);
+            merchant_list.update_merchant(current_merchant);
+            continue;
+        }
     }
 
     // Calculate more stats without merchant data analyzing
+    //  Store closures are not uninstalls, their growth is apart
     total_stats.set_merchant_growth(
-        *total_stats.installed_count() as i32 + *total_stats.store_reopened_count() as i32
-            - *total_stats.uninstalled_count() as i32
-            - *total_stats.store_closed_count() as i32,
+        *total_stats.installed_count() as i32 - *total_stats.uninstalled_count() as i32,
+    );
+    total_stats.set_store_closure_growth(
+        *total_stats.store_reopened_count() as i32 - *total_stats.store_closed_count() as i32,
     );
 
     total_stats.set_total_churn_rate(if *total_stats.installed_count() > 0 {
@@ -168,18 +227,23 @@ fn analyze_details(
     //  Process merchant data
     for merchant in merchant_list.merchants_mut().values_mut() {
         //  Updated installed status
-        match *merchant.installed_count() as i32 + *merchant.store_reopened_count() as i32
-            - *merchant.uninstalled_count() as i32
-            - *merchant.store_closed_count() as i32
-        {
+        match *merchant.installed_count() as i32 - *merchant.uninstalled_count() as i32 {
             delta if delta > 0 => {
                 merchant.set_installed_status(INSTALLED_STRING.to_string());
             }
             delta if delta < 0 => {
                 merchant.set_installed_status(UNINSTALLED_STRING.to_string());
-                if merchant.installing_events().len() > 0
-                    && merchant.installing_events().first().unwrap().event() == UNINSTALLED_STRING
-                {
+                let installed_before = if *merchant.carried_over() {
+                    *merchant.active_at_start()
+                } else {
+                    !merchant.installing_events().is_empty()
+                        && merchant
+                            .installing_events()
+                            .first()
+                            .unwrap()
+                            .is(EventCategory::Uninstalled)
+                };
+                if installed_before {
                     merchant.set_installed_status(UNINSTALLED_OLD_STRING.to_string());
                     total_stats.increase_old_uninstalled_count(1);
                 }
@@ -189,12 +253,44 @@ fn analyze_details(
             }
         }
 
-        //  Determine final subscription status
+        //  A subscription whose last state change is a freeze is paused, it is neither new nor canceled
+        let mut state_events: Vec<AppEvent> = merchant.subscription_events().clone();
+        state_events.sort_by_key(|e| *e.time());
+
+        let frozen_at_end = state_events
+            .iter()
+            .rev()
+            .find(|e| {
+                e.is(EventCategory::SubscriptionActivated)
+                    || e.is(EventCategory::SubscriptionCanceled)
+                    || e.is(EventCategory::SubscriptionFrozen)
+                    || e.is(EventCategory::SubscriptionUnfrozen)
+            })
+            .is_some_and(|e| e.is(EventCategory::SubscriptionFrozen));
+
+        if state_events
+            .iter()
+            .skip_while(|e| !e.is(EventCategory::SubscriptionFrozen))
+            .any(|e| e.is(EventCategory::SubscriptionCanceled))
+        {
+            total_stats.increase_frozen_then_canceled_sub_count(1);
+        }
+
+        //  Determine final subscription status, a subscription activated and then frozen still counts as new
+        let mut new_sub = false;
         match *merchant.subscription_activated_count() as i32
             - *merchant.subscription_canceled_count() as i32
         {
+            delta if delta >= 0 && frozen_at_end => {
+                merchant.set_subscription_status(SUBSCRIPTION_STATUS_FROZEN.to_string());
+                if delta > 0 {
+                    new_sub = true;
+                    total_stats.increase_new_sub_count(1);
+                }
+            }
             delta if delta > 0 => {
                 merchant.set_subscription_status(SUBSCRIPTION_STATUS_ACTIVE.to_string());
+                new_sub = true;
                 total_stats.increase_new_sub_count(1);
             }
             delta if delta < 0 => {
@@ -209,7 +305,7 @@ fn analyze_details(
         //  Determine new subscription details
         for event in merchant.clone().subscription_events().iter().rev() {
             //  Use reverse order to get the latest activated event
-            if SUBSCRIPTION_ACTIVATED_STRINGS.contains(&event.event().as_str()) {
+            if event.is(EventCategory::SubscriptionActivated) {
                 //  Determine plan
                 for plan in pricing_defs.subscriptions() {
                     let mut re = if case_sensitive_regex {
@@ -249,9 +345,18 @@ fn analyze_details(
                                 1,
                             )
                             .unwrap(); // Always Ok, because pricing definitions are not modified anywhere in whole program
+                        total_stats
+                            .split_sub_stats_details_mut(plan)
+                            .all_new_sub_mut()
+                            .increase(
+                                plan,
+                                merchant.last_new_sub_billing_cycle().as_ref().unwrap(),
+                                1,
+                            )
+                            .unwrap(); // Always Ok, because pricing definitions are not modified anywhere in whole program
 
-                        //  Determine if the event stands for an active subscription
-                        if merchant.subscription_status() == SUBSCRIPTION_STATUS_ACTIVE {
+                        //  Determine if the event stands for a new subscription
+                        if new_sub {
                             total_stats
                                 .sub_stats_details_mut()
                                 .new_sub_mut()
@@ -261,6 +366,15 @@ fn analyze_details(
                                     1,
                                 )
                                 .unwrap(); // Always Ok, because pricing definitions are not modified anywhere in whole program
+                            total_stats
+                                .split_sub_stats_details_mut(plan)
+                                .new_sub_mut()
+                                .increase(
+                                    plan,
+                                    merchant.last_new_sub_billing_cycle().as_ref().unwrap(),
+                                    1,
+                                )
+                                .unwrap(); // Always Ok, because pricing definitions are not modified anywhere in whole program
                         }
 
                         break;
@@ -274,7 +388,7 @@ fn analyze_details(
         //  Determine canceled subscription details
         for event in merchant.clone().subscription_events().iter() {
             //  Use normal order to get the earliest canceled event
-            if SUBSCRIPTION_CANCELED_STRINGS.contains(&event.event().as_str()) {
+            if event.is(EventCategory::SubscriptionCanceled) {
                 //  Determine plan
                 for plan in pricing_defs.subscriptions() {
                     let mut re = Regex::new(plan.regex_pattern().as_str()).unwrap();
@@ -303,6 +417,18 @@ fn analyze_details(
                                 1,
                             )
                             .unwrap(); // Always Ok, because pricing definitions are not modified anywhere in whole program
+                        total_stats
+                            .split_sub_stats_details_mut(plan)
+                            .all_canceled_sub_mut()
+                            .increase(
+                                plan,
+                                merchant
+                                    .first_canceled_sub_billing_cycle()
+                                    .as_ref()
+                                    .unwrap(),
+                                1,
+                            )
+                            .unwrap(); // Always Ok, because pricing definitions are not modified anywhere in whole program
 
                         //  Determine if the event stands for a canceled subscription
                         if merchant.subscription_status() == SUBSCRIPTION_STATUS_CANCELED {
@@ -318,6 +444,18 @@ fn analyze_details(
                                     1,
                                 )
                                 .unwrap(); // Always Ok, because pricing definitions are not modified anywhere in whole program
+                            total_stats
+                                .split_sub_stats_details_mut(plan)
+                                .canceled_sub_mut()
+                                .increase(
+                                    plan,
+                                    merchant
+                                        .first_canceled_sub_billing_cycle()
+                                        .as_ref()
+                                        .unwrap(),
+                                    1,
+                                )
+                                .unwrap(); // Always Ok, because pricing definitions are not modified anywhere in whole program
                         }
 
                         break;
@@ -328,290 +466,2390 @@ fn analyze_details(
             }
         }
 
-        //  Update final total data
-        total_stats.set_churn_rate(if *total_stats.installed_count() > 0 {
-            (*total_stats.uninstalled_count() as f64 - *total_stats.old_uninstalled_count() as f64)
-                / *total_stats.installed_count() as f64
-                * 100.0
-        } else {
-            0.0
-        });
+        //  Split new and canceled subscriptions into paying and free.
+        //  Subscriptions with unknown plan are considered paying
+        if new_sub {
+            if merchant
+                .last_new_sub_plan()
+                .as_ref()
+                .is_none_or(|p| p.is_paying())
+            {
+                total_stats.increase_new_paying_sub_count(1);
+            } else {
+                total_stats.increase_new_free_sub_count(1);
+            }
+        } else if merchant.subscription_status() == SUBSCRIPTION_STATUS_CANCELED {
+            if merchant
+                .first_canceled_sub_plan()
+                .as_ref()
+                .is_none_or(|p| p.is_paying())
+            {
+                total_stats.increase_canceled_paying_sub_count(1);
+            } else {
+                total_stats.increase_canceled_free_sub_count(1);
+            }
+        }
+    }
 
-        total_stats.set_sub_growth(
-            *total_stats.new_sub_count() as i32 - *total_stats.canceled_sub_count() as i32,
-        );
+    //  Update final total data
+    total_stats.set_churn_rate(if *total_stats.installed_count() > 0 {
+        (*total_stats.uninstalled_count() as f64 - *total_stats.old_uninstalled_count() as f64)
+            / *total_stats.installed_count() as f64
+            * 100.0
+    } else {
+        0.0
+    });
 
-        total_stats
-            .set_paid_growth(total_stats.sub_growth() + *total_stats.one_time_count() as i32);
+    total_stats.set_sub_growth(
+        *total_stats.new_sub_count() as i32 - *total_stats.canceled_sub_count() as i32,
+    );
 
-        //  Calculate subscription growth details
-        let mut calculated_result: IndexMap<String, i32> = IndexMap::new();
+    total_stats.set_paying_sub_growth(
+        *total_stats.new_paying_sub_count() as i32
+            - *total_stats.canceled_paying_sub_count() as i32,
+    );
 
-        //  Yearly
-        for (plan, new_count) in total_stats
-            .clone()
-            .sub_stats_details()
-            .new_sub()
-            .yearly_counts()
-        {
-            let canceled_count = total_stats
-                .sub_stats_details()
-                .canceled_sub()
-                .yearly_counts()
-                .get(plan)
-                .unwrap_or(&0);
-            calculated_result.insert(plan.to_string(), new_count - canceled_count);
-        }
-        total_stats
-            .sub_stats_details_mut()
-            .sub_growth_mut()
-            .set_yearly_counts(calculated_result.clone());
+    total_stats.set_free_sub_growth(
+        *total_stats.new_free_sub_count() as i32 - *total_stats.canceled_free_sub_count() as i32,
+    );
 
-        //  Monthly
-        calculated_result.clear();
-        for (plan, new_count) in total_stats
-            .clone()
-            .sub_stats_details()
-            .new_sub()
-            .monthly_counts()
-        {
-            let canceled_count = total_stats
-                .sub_stats_details()
-                .canceled_sub()
-                .monthly_counts()
-                .get(plan)
-                .unwrap_or(&0);
-            calculated_result.insert(plan.to_string(), new_count - canceled_count);
-        }
-        total_stats
-            .sub_stats_details_mut()
-            .sub_growth_mut()
-            .set_monthly_counts(calculated_result.clone());
-    }
+    //  Free plans do not bring any money, so only paying subscriptions count as paid growth
+    total_stats
+        .set_paid_growth(total_stats.paying_sub_growth() + *total_stats.one_time_count() as i32);
+
+    //  Calculate subscription growth details
+    total_stats.sub_stats_details_mut().calculate_sub_growth();
+    total_stats
+        .paying_sub_stats_details_mut()
+        .calculate_sub_growth();
+    total_stats
+        .free_sub_stats_details_mut()
+        .calculate_sub_growth();
 }
 
-/// Analyze event list and return final data
-fn analyze_events_list(
-    event_list: &Vec<AppEvent>,
-    pricing_defs: &PricingDefs,
-    excluding_defs: &ExcludingDef,
+/// Find the pricing unit whose regex pattern matches the given text
+fn find_pricing_unit<'a>(
+    text: &str,
+    pricing_units: &'a [PricingUnit],
     case_sensitive_regex: bool,
-) -> anyhow::Result<(TotalStats, MerchantList)> {
-    let (mut total_stats, mut merchant_data) = build_base_data(
-        event_list,
-        pricing_defs,
-        excluding_defs,
-        case_sensitive_regex,
-    );
+) -> Option<&'a PricingUnit> {
+    pricing_units.iter().find(|unit| {
+        if case_sensitive_regex {
+            Regex::new(unit.regex_pattern().as_str())
+                .unwrap()
+                .is_match(text)
+        } else {
+            Regex::new(unit.regex_pattern().to_lowercase().as_str())
+                .unwrap()
+                .is_match(text.to_lowercase().as_str())
+        }
+    })
+}
 
-    analyze_details(
-        &mut total_stats,
-        &mut merchant_data,
-        pricing_defs,
-        case_sensitive_regex,
-    );
+/// Determine billing cycle from subscription event details
+fn find_billing_cycle(details: &str, case_sensitive_regex: bool) -> BillingCycle {
+    let is_yearly = if case_sensitive_regex {
+        Regex::new(YEARLY_PATTERN).unwrap().is_match(details)
+    } else {
+        Regex::new(YEARLY_PATTERN.to_lowercase().as_str())
+            .unwrap()
+            .is_match(details.to_lowercase().as_str())
+    };
 
-    Ok((total_stats, merchant_data))
+    if is_yearly {
+        BillingCycle::Yearly
+    } else {
+        BillingCycle::Monthly
+    }
 }
 
-fn analyze_file(
-    event_history_file: &PathBuf,
-    pricing_defs: &PricingDefs,
-    excluding_defs: &ExcludingDef,
-    case_sensitive_regex: bool,
-    out_folder: &PathBuf,
-    out_file_total_stats_pref: &str,
-    out_file_merchant_data_pref: &Option<String>,
-    out_file_app_events_pref: &Option<String>,
-) -> anyhow::Result<String> {
-    let event_list: Vec<AppEvent> =
-        read_events_from_csv(event_history_file, &excluding_defs.excluding_field())?;
+/// Estimated revenue of a subscription billed from `billing_start` to `end`.
+/// Every started billing cycle is charged in full.
+fn estimate_subscription_revenue(
+    plan: &PricingUnit,
+    billing_cycle: &BillingCycle,
+    billing_start: NaiveDateTime,
+    end: NaiveDateTime,
+) -> f64 {
+    if end <= billing_start {
+        return 0.0;
+    }
 
-    let (total_stats, merchant_data) = analyze_events_list(
-        &event_list,
-        pricing_defs,
-        excluding_defs,
-        case_sensitive_regex,
-    )?;
+    let billed_days = (end - billing_start).num_seconds() as f64 / 86400.0;
+    let cycle_days = match billing_cycle {
+        BillingCycle::Monthly => DAYS_PER_MONTH,
+        BillingCycle::Yearly => DAYS_PER_YEAR,
+    };
 
-    let mut message_success: String;
+    (billed_days / cycle_days).ceil() * plan.cycle_price(billing_cycle)
+}
 
-    let out_file_total_stats: PathBuf = out_folder.join(format!(
-        "{}_{}_{}.json",
-        out_file_total_stats_pref,
-        total_stats.start_time_str(),
-        total_stats.end_time_str()
-    ));
+/// Average of the given values, 0 if there is no value
+fn average(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
 
-    match write_total_stats_to_json(&out_file_total_stats, &total_stats) {
-        Ok(()) => {
-            message_success = data::TOTAL_STATS.to_string()
-                + message::success::SPECIFIC_DATA_WRITTEN_FILE
-                + out_file_total_stats.display().to_string().as_str()
-        }
-        Err(e) => return Err(e),
+/// Median of the given values, 0 if there is no value
+fn median(values: &mut [f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
     }
 
-    if let Some(pref) = out_file_merchant_data_pref {
-        let out_file_merchant_data: PathBuf = out_folder.join(format!(
-            "{}_{}_{}.json",
-            pref,
-            total_stats.start_time_str(),
-            total_stats.end_time_str()
-        ));
-        match write_merchant_data_to_json(&out_file_merchant_data, &merchant_data) {
-            Ok(()) => {
-                message_success = message_success
-                    + data::MERCHANT_DATA
-                    + message::success::SPECIFIC_DATA_WRITTEN_FILE
-                    + out_file_merchant_data.display().to_string().as_str()
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+/// Analyze free trials from merchant data
+/// A trial starts with the first activation of a plan having trial days.
+/// It is converted once a paid plan is still active at the end of the trial
+/// (or as soon as a paid plan is activated when trialing a free plan).
+fn analyze_trials(
+    total_stats: &mut TotalStats,
+    merchant_list: &mut MerchantList,
+    pricing_defs: &PricingDefs,
+    case_sensitive_regex: bool,
+) {
+    let data_end_time: Option<NaiveDateTime> = *merchant_list.end_time();
+    let mut days_to_conversion_list: Vec<f64> = Vec::new();
+
+    for merchant in merchant_list.merchants_mut().values_mut() {
+        //  Find the trial start
+        let mut trial: Option<(NaiveDateTime, PricingUnit)> = None;
+        for event in merchant.subscription_events() {
+            if !event.is(EventCategory::SubscriptionActivated) {
+                continue;
+            }
+            if let (Some(time), Some(plan)) = (
+                event.time(),
+                find_pricing_unit(
+                    event.details(),
+                    pricing_defs.subscriptions(),
+                    case_sensitive_regex,
+                ),
+            ) && *plan.trial_days() > 0
+            {
+                trial = Some((*time, plan.clone()));
+                break;
             }
-            Err(e) => return Err(e),
         }
-    }
 
-    if let Some(pref) = out_file_app_events_pref {
-        let out_file_app_events: PathBuf = out_folder.join(format!(
-            "{}_{}_{}.json",
-            pref,
-            total_stats.start_time_str(),
-            total_stats.end_time_str()
-        ));
-        match write_app_event_list_to_json(&out_file_app_events, &event_list) {
-            Ok(()) => {
-                message_success = message_success
-                    + data::APP_EVENTS
-                    + message::success::SPECIFIC_DATA_WRITTEN_FILE
-                    + out_file_app_events.display().to_string().as_str()
+        let Some((trial_started_time, trial_plan)) = trial else {
+            continue;
+        };
+        let trial_end_time = trial_started_time + Duration::days(*trial_plan.trial_days() as i64);
+
+        total_stats.trial_stats_mut().increase_started_count(1);
+        merchant.set_trial_started_time(Some(trial_started_time));
+        merchant.set_trial_plan(Some(trial_plan.clone()));
+
+        //  Replay events happening during the trial
+        let mut trial_events: Vec<&AppEvent> = merchant
+            .subscription_events()
+            .iter()
+            .chain(merchant.installing_events().iter())
+            .filter(|e| {
+                e.time()
+                    .is_some_and(|t| t > trial_started_time && t < trial_end_time)
+            })
+            .collect();
+        trial_events.sort_by_key(|e| *e.time());
+
+        let mut active_plan: Option<PricingUnit> = Some(trial_plan.clone());
+        let mut uninstalled = false;
+        let mut converted_time: Option<NaiveDateTime> = None;
+
+        for event in trial_events {
+            if event.is(EventCategory::SubscriptionActivated) {
+                active_plan = find_pricing_unit(
+                    event.details(),
+                    pricing_defs.subscriptions(),
+                    case_sensitive_regex,
+                )
+                .cloned();
+                uninstalled = false;
+
+                //  Upgrading from a free trial plan to a paid plan converts immediately
+                if !trial_plan.is_paying() && active_plan.as_ref().is_some_and(|p| p.is_paying()) {
+                    converted_time = *event.time();
+                    break;
+                }
+            } else if event.is(EventCategory::SubscriptionUnfrozen) {
+                active_plan = find_pricing_unit(
+                    event.details(),
+                    pricing_defs.subscriptions(),
+                    case_sensitive_regex,
+                )
+                .cloned();
+            } else if event.is(EventCategory::SubscriptionCanceled)
+                || event.is(EventCategory::SubscriptionFrozen)
+            {
+                active_plan = None;
+            } else if event.is(EventCategory::Uninstalled) {
+                active_plan = None;
+                uninstalled = true;
             }
-            Err(e) => return Err(e),
+        }
+
+        let trial_ended = data_end_time.is_some_and(|t| t >= trial_end_time);
+
+        if converted_time.is_none()
+            && trial_ended
+            && active_plan.as_ref().is_some_and(|p| p.is_paying())
+        {
+            converted_time = Some(trial_end_time);
+        }
+
+        if let Some(t) = converted_time {
+            merchant.set_trial_status(TRIAL_STATUS_CONVERTED.to_string());
+            merchant.set_trial_converted_time(Some(t));
+            total_stats.trial_stats_mut().increase_converted_count(1);
+            days_to_conversion_list.push((t - trial_started_time).num_seconds() as f64 / 86400.0);
+        } else if uninstalled {
+            merchant.set_trial_status(TRIAL_STATUS_UNINSTALLED.to_string());
+            total_stats.trial_stats_mut().increase_uninstalled_count(1);
+        } else if active_plan.is_none() || trial_ended {
+            merchant.set_trial_status(TRIAL_STATUS_EXPIRED.to_string());
+            total_stats.trial_stats_mut().increase_expired_count(1);
+        } else {
+            merchant.set_trial_status(TRIAL_STATUS_TRIALING.to_string());
+            total_stats.trial_stats_mut().increase_ongoing_count(1);
         }
     }
 
-    Ok(message_success)
+    let trial_stats = total_stats.trial_stats_mut();
+    let ended_count = trial_stats.converted_count()
+        + trial_stats.expired_count()
+        + trial_stats.uninstalled_count();
+    trial_stats.set_conversion_rate(if ended_count > 0 {
+        *trial_stats.converted_count() as f64 / ended_count as f64 * 100.0
+    } else {
+        0.0
+    });
+    trial_stats.set_median_days_to_conversion(median(&mut days_to_conversion_list));
 }
 
-pub fn analyze_from_gui(
-    event_history_file_list: &Option<Vec<PathBuf>>,
-    selected_pricing_defs_option: &UiOption,
-    selected_excluding_defs_option: &UiOption,
-    pricing_defs_file: &Option<PathBuf>,
-    excluding_defs_file: &Option<PathBuf>,
-    debug_mode: bool,
+/// Days between consecutive purchases, purchase times must be sorted
+fn days_between(purchase_times: &[NaiveDateTime]) -> Vec<f64> {
+    purchase_times
+        .windows(2)
+        .map(|w| (w[1] - w[0]).num_seconds() as f64 / 86400.0)
+        .collect()
+}
+
+/// Analyze one-time revenue and repeat purchases per pack and for all packs
+fn analyze_one_times(
+    total_stats: &mut TotalStats,
+    merchant_list: &MerchantList,
+    pricing_defs: &PricingDefs,
     case_sensitive_regex: bool,
-) -> anyhow::Result<String> {
-    let pricing_defs: PricingDefs;
-    let excluding_defs: ExcludingDef;
+) {
+    let mut all_days_between: Vec<f64> = Vec::new();
+    let mut pack_days_between: IndexMap<String, Vec<f64>> = IndexMap::new();
 
-    pricing_defs = match selected_pricing_defs_option {
-        d if d.value() == ui::OPTION_CUSTOM.value() => {
-            if let Some(f) = pricing_defs_file {
-                read_pricing_def_from_json(&f)?
-            } else {
-                return Err(anyhow!(
-                    "{} {} {}!",
-                    data::KIND_CUSTOM.to_string(),
-                    data::PRICING_DEFS,
-                    message::error::FILE_NOT_CHOSEN
-                ));
-            }
+    for merchant in merchant_list.merchants().values() {
+        if merchant.one_time_events().is_empty() {
+            continue;
         }
-        _ => read_pricing_def_from_json_str(
-            selected_pricing_defs_option
-                .connected_data()
-                .as_ref()
-                .unwrap(),
-        )?,
-    };
 
-    excluding_defs = match selected_excluding_defs_option {
-        d if d.value() == ui::OPTION_CUSTOM.value() => {
-            if let Some(f) = excluding_defs_file {
-                read_excluding_def_from_json(&f)?
-            } else {
-                return Err(anyhow!(
-                    "{} {} {}!",
-                    data::KIND_CUSTOM.to_string(),
-                    data::EXCLUDING_DEFS,
-                    message::error::FILE_NOT_CHOSEN
-                ));
+        let mut all_purchase_times: Vec<NaiveDateTime> = Vec::new();
+        let mut all_revenue: f64 = 0.0;
+        let mut pack_purchases: IndexMap<String, (Vec<NaiveDateTime>, f64)> = IndexMap::new();
+
+        //  Purchases without time are left out, so counts, revenue and repeat intervals agree
+        for event in merchant.one_time_events() {
+            let Some(time) = event.time() else {
+                continue;
+            };
+            let Some(pack) = find_pricing_unit(
+                event.details(),
+                pricing_defs.one_times(),
+                case_sensitive_regex,
+            ) else {
+                continue;
+            };
+            let price = if pack.is_paying() { *pack.price() } else { 0.0 };
+
+            let entry = pack_purchases
+                .entry(pack.code().clone())
+                .or_insert((Vec::new(), 0.0));
+            entry.0.push(*time);
+            entry.1 += price;
+            all_purchase_times.push(*time);
+            all_revenue += price;
+        }
+
+        if pack_purchases.is_empty() {
+            continue;
+        }
+
+        for (code, (mut purchase_times, revenue)) in pack_purchases {
+            purchase_times.sort();
+            pack_days_between
+                .entry(code.clone())
+                .or_default()
+                .extend(days_between(&purchase_times));
+
+            if let Some(pack_stats) = total_stats.one_time_pack_stats_mut().get_mut(&code) {
+                pack_stats.add_buyer(purchase_times.len() as u32, revenue);
             }
         }
-        _ => read_excluding_def_from_json_str(
-            selected_excluding_defs_option
-                .connected_data()
-                .as_ref()
-                .unwrap(),
-        )?,
-    };
 
-    if let Some(f_list) = event_history_file_list {
-        let out_folder: PathBuf = std::env::current_dir()?.join(data::OUT_FOLDER_NAME);
-        let mut final_error_message: String = String::from("");
-        let mut final_success_message: String = format!(
-            "{} {}\n",
-            data::TOTAL_STATS.to_string(),
-            message::success::SPECIFIC_DATA_WRITTEN_FILE
-        );
+        all_purchase_times.sort();
+        all_days_between.extend(days_between(&all_purchase_times));
+        total_stats
+            .one_time_summary_mut()
+            .add_buyer(all_purchase_times.len() as u32, all_revenue);
+    }
 
-        let out_file_total_stats_pref: String = data::TOTAL_STATS
-            .to_string()
-            .replace(" ", "_")
-            .to_lowercase();
-
-        let mut out_file_merchant_data_pref: Option<String> = None;
-        let mut out_file_app_events_pref: Option<String> = None;
-
-        if debug_mode {
-            final_success_message += format!(
-                "{} {}\n{} {}",
-                data::MERCHANT_DATA,
-                message::success::SPECIFIC_DATA_WRITTEN_FILE,
-                data::APP_EVENTS,
-                message::success::SPECIFIC_DATA_WRITTEN_FILE
+    for (code, pack_stats) in total_stats.one_time_pack_stats_mut().iter_mut() {
+        if let Some(days) = pack_days_between.get_mut(code) {
+            pack_stats.set_average_days_between_purchases(average(days));
+            pack_stats.set_median_days_between_purchases(median(days));
+        }
+    }
+
+    total_stats
+        .one_time_summary_mut()
+        .set_average_days_between_purchases(average(&all_days_between));
+    total_stats
+        .one_time_summary_mut()
+        .set_median_days_between_purchases(median(&mut all_days_between));
+}
+
+/// Analyze revenue, tenure and lifetime value from merchant data.
+/// Revenue is estimated from pricing definitions because events do not carry any amount.
+fn analyze_revenue(
+    total_stats: &mut TotalStats,
+    merchant_list: &mut MerchantList,
+    pricing_defs: &PricingDefs,
+    case_sensitive_regex: bool,
+) {
+    let (Some(data_start_time), Some(data_end_time)) =
+        (*merchant_list.start_time(), *merchant_list.end_time())
+    else {
+        return;
+    };
+
+    for merchant in merchant_list.merchants_mut().values_mut() {
+        //  Subscription revenue: replay subscription periods
+        let mut timeline: Vec<&AppEvent> = merchant
+            .subscription_events()
+            .iter()
+            .chain(
+                merchant
+                    .installing_events()
+                    .iter()
+                    .filter(|e| e.is(EventCategory::Uninstalled)),
             )
-            .as_str();
+            .filter(|e| e.time().is_some())
+            .collect();
+        timeline.sort_by_key(|e| *e.time());
+
+        let mut subscription_revenue: f64 = 0.0;
+
+        //  Unfrozen before any other state change means frozen at period start, not billed until then
+        let frozen_at_start = timeline
+            .iter()
+            .find(|e| {
+                e.is(EventCategory::SubscriptionActivated)
+                    || e.is(EventCategory::SubscriptionFrozen)
+                    || e.is(EventCategory::SubscriptionUnfrozen)
+            })
+            .is_some_and(|e| e.is(EventCategory::SubscriptionUnfrozen));
+
+        //  A subscription active at period start is billed from period start, its trial is already over
+        let mut current_sub: Option<(NaiveDateTime, PricingUnit, BillingCycle)> = merchant
+            .sub_plan_at_start()
+            .clone()
+            .zip(merchant.sub_billing_cycle_at_start().clone())
+            .filter(|_| !frozen_at_start)
+            .map(|(plan, billing_cycle)| (data_start_time, plan, billing_cycle));
 
-            out_file_merchant_data_pref = Some(
-                data::MERCHANT_DATA
-                    .to_string()
-                    .replace(" ", "_")
-                    .to_lowercase(),
-            );
+        for event in timeline {
+            let time = event.time().unwrap();
 
-            out_file_app_events_pref = Some(
-                data::APP_EVENTS
-                    .to_string()
-                    .replace(" ", "_")
-                    .to_lowercase(),
-            );
-        }
+            if let Some((start, plan, billing_cycle)) = current_sub.take() {
+                subscription_revenue +=
+                    estimate_subscription_revenue(&plan, &billing_cycle, start, time);
+            }
 
-        for f in f_list {
-            if let Err(e) = analyze_file(
-                f,
-                &pricing_defs,
-                &excluding_defs,
-                case_sensitive_regex,
-                &out_folder,
-                &out_file_total_stats_pref,
-                &out_file_merchant_data_pref,
-                &out_file_app_events_pref,
-            ) {
-                final_error_message += e.to_string().as_str();
+            if event.is(EventCategory::SubscriptionActivated) {
+                if let Some(plan) = find_pricing_unit(
+                    event.details(),
+                    pricing_defs.subscriptions(),
+                    case_sensitive_regex,
+                ) {
+                    //  Billing starts after the trial
+                    current_sub = Some((
+                        time + Duration::days(*plan.trial_days() as i64),
+                        plan.clone(),
+                        find_billing_cycle(event.details(), case_sensitive_regex),
+                    ));
+                }
+            } else if event.is(EventCategory::SubscriptionUnfrozen) {
+                //  Billing resumes right away, a frozen period is not billed
+                if let Some(plan) = find_pricing_unit(
+                    event.details(),
+                    pricing_defs.subscriptions(),
+                    case_sensitive_regex,
+                ) {
+                    current_sub = Some((
+                        time,
+                        plan.clone(),
+                        find_billing_cycle(event.details(), case_sensitive_regex),
+                    ));
+                }
             }
         }
 
-        if final_error_message != "" {
-            return Err(anyhow!(final_error_message));
+        if let Some((start, plan, billing_cycle)) = current_sub {
+            subscription_revenue +=
+                estimate_subscription_revenue(&plan, &billing_cycle, start, data_end_time);
         }
 
-        return Ok(final_success_message);
-    } else {
-        return Err(anyhow!(
-            "{} {}!",
-            data::APP_EVENTS.to_string(),
-            message::error::FILE_NOT_CHOSEN
-        ));
+        //  One-time revenue, adding 0.0 turns the -0.0 of an empty sum into 0
+        let one_time_revenue: f64 = merchant
+            .one_time_events()
+            .iter()
+            .filter_map(|e| {
+                find_pricing_unit(e.details(), pricing_defs.one_times(), case_sensitive_regex)
+            })
+            .filter(|pack| pack.is_paying())
+            .map(|pack| *pack.price())
+            .sum::<f64>()
+            + 0.0;
+
+        //  Tenure: from first install (or data start if installed before) to last uninstall (or data end)
+        let first_installed_time = merchant
+            .installing_events()
+            .iter()
+            .find(|e| e.is(EventCategory::Installed))
+            .and_then(|e| *e.time());
+
+        let last_uninstalled_event = merchant
+            .installing_events()
+            .iter()
+            .rev()
+            .find(|e| e.is(EventCategory::Installed) || e.is(EventCategory::Uninstalled))
+            .filter(|e| e.is(EventCategory::Uninstalled));
+
+        let uninstalled = last_uninstalled_event.is_some();
+        let tenure_end_time = last_uninstalled_event
+            .and_then(|e| *e.time())
+            .unwrap_or(data_end_time);
+
+        let tenure_days = (tenure_end_time - first_installed_time.unwrap_or(data_start_time))
+            .num_seconds()
+            .max(0) as f64
+            / 86400.0;
+
+        merchant.set_subscription_revenue(subscription_revenue);
+        merchant.set_one_time_revenue(one_time_revenue);
+        merchant.set_lifetime_revenue(subscription_revenue + one_time_revenue);
+        merchant.set_tenure_days(tenure_days);
+
+        total_stats.revenue_stats_mut().add_merchant(merchant);
+
+        //  Install cohort, only merchants installed in the period have a known lifetime start
+        if let Some(t) = first_installed_time {
+            total_stats
+                .revenue_stats_mut()
+                .cohorts_mut()
+                .entry(t.format(COHORT_PATTERN).to_string())
+                .or_default()
+                .add_merchant(*merchant.lifetime_revenue(), tenure_days, uninstalled);
+        }
+    }
+
+    total_stats.revenue_stats_mut().cohorts_mut().sort_keys();
+    total_stats.revenue_stats_mut().calculate_averages();
+}
+
+/// Detect merchants coming back: reinstalling after uninstall, re-subscribing after cancel.
+/// Both may happen for the same return, they are counted separately.
+fn analyze_reactivations(
+    total_stats: &mut TotalStats,
+    merchant_list: &mut MerchantList,
+    pricing_defs: &PricingDefs,
+    case_sensitive_regex: bool,
+) {
+    let mut reinstall_gap_days_list: Vec<f64> = Vec::new();
+    let mut resubscribe_gap_days_list: Vec<f64> = Vec::new();
+
+    for merchant in merchant_list.merchants_mut().values_mut() {
+        let mut timeline: Vec<AppEvent> = merchant
+            .subscription_events()
+            .iter()
+            .chain(
+                merchant
+                    .installing_events()
+                    .iter()
+                    .filter(|e| e.is(EventCategory::Installed) || e.is(EventCategory::Uninstalled)),
+            )
+            .filter(|e| e.time().is_some())
+            .cloned()
+            .collect();
+        timeline.sort_by_key(|e| *e.time());
+
+        let mut last_plan: Option<String> = None;
+        let mut last_uninstalled: Option<(NaiveDateTime, Option<String>)> = None;
+        let mut last_canceled: Option<(NaiveDateTime, Option<String>)> = None;
+        let mut waiting_reinstall_plan = false;
+
+        for event in timeline.iter() {
+            let time = event.time().unwrap();
+
+            if event.is(EventCategory::Uninstalled) {
+                last_uninstalled = Some((time, last_plan.clone()));
+                waiting_reinstall_plan = false;
+            } else if event.is(EventCategory::Installed) {
+                if let Some((lost_time, previous_plan)) = last_uninstalled.take() {
+                    merchant.push_reactivation(Reactivation::new(
+                        REACTIVATION_KIND_REINSTALL,
+                        lost_time,
+                        time,
+                        previous_plan,
+                        None,
+                    ));
+                    waiting_reinstall_plan = true;
+                }
+            } else if event.is(EventCategory::SubscriptionActivated) {
+                let plan = find_pricing_unit(
+                    event.details(),
+                    pricing_defs.subscriptions(),
+                    case_sensitive_regex,
+                )
+                .map(|p| p.code().clone());
+
+                if waiting_reinstall_plan {
+                    if let Some(reactivation) = merchant.last_reactivation_mut() {
+                        reactivation.set_returned_plan(plan.clone());
+                    }
+                    waiting_reinstall_plan = false;
+                }
+
+                if let Some((lost_time, previous_plan)) = last_canceled.take()
+                    && time - lost_time >= Duration::hours(RESUBSCRIBE_MIN_GAP_HOURS)
+                {
+                    merchant.push_reactivation(Reactivation::new(
+                        REACTIVATION_KIND_RESUBSCRIBE,
+                        lost_time,
+                        time,
+                        previous_plan,
+                        plan.clone(),
+                    ));
+                }
+
+                last_plan = plan;
+            } else if event.is(EventCategory::SubscriptionCanceled) {
+                let plan = find_pricing_unit(
+                    event.details(),
+                    pricing_defs.subscriptions(),
+                    case_sensitive_regex,
+                )
+                .map(|p| p.code().clone())
+                .or(last_plan.clone());
+
+                last_canceled = Some((time, plan));
+            }
+        }
+
+        for reactivation in merchant.reactivations() {
+            if reactivation.kind() == REACTIVATION_KIND_REINSTALL {
+                reinstall_gap_days_list.push(*reactivation.gap_days());
+            } else {
+                resubscribe_gap_days_list.push(*reactivation.gap_days());
+            }
+        }
+
+        total_stats.reactivation_stats_mut().add_merchant(merchant);
+    }
+
+    let reactivation_stats = total_stats.reactivation_stats_mut();
+    reactivation_stats.set_average_reinstall_gap_days(average(&reinstall_gap_days_list));
+    reactivation_stats.set_median_reinstall_gap_days(median(&mut reinstall_gap_days_list));
+    reactivation_stats.set_average_resubscribe_gap_days(average(&resubscribe_gap_days_list));
+    reactivation_stats.set_median_resubscribe_gap_days(median(&mut resubscribe_gap_days_list));
+}
+
+/// Analyze time from first install to first subscription, first cancel and uninstall.
+/// Only merchants installed in the period are counted.
+fn analyze_time_to_events(total_stats: &mut TotalStats, merchant_list: &mut MerchantList) {
+    let hours_between =
+        |from: NaiveDateTime, to: NaiveDateTime| (to - from).num_seconds() as f64 / 3600.0;
+
+    for merchant in merchant_list.merchants_mut().values_mut() {
+        let Some(installed_time) = merchant
+            .installing_events()
+            .iter()
+            .find(|e| e.is(EventCategory::Installed))
+            .and_then(|e| *e.time())
+        else {
+            continue;
+        };
+
+        let first_time_after = |events: &Vec<AppEvent>, category: EventCategory| {
+            events
+                .iter()
+                .filter(|e| e.is(category))
+                .filter_map(|e| *e.time())
+                .filter(|t| *t >= installed_time)
+                .min()
+        };
+
+        let hours_to_first_subscription = first_time_after(
+            merchant.subscription_events(),
+            EventCategory::SubscriptionActivated,
+        )
+        .map(|t| hours_between(installed_time, t));
+        let hours_to_first_cancel = first_time_after(
+            merchant.subscription_events(),
+            EventCategory::SubscriptionCanceled,
+        )
+        .map(|t| hours_between(installed_time, t));
+        let hours_to_uninstall =
+            first_time_after(merchant.installing_events(), EventCategory::Uninstalled)
+                .map(|t| hours_between(installed_time, t));
+
+        let time_to_event_stats = total_stats.time_to_event_stats_mut();
+        if let Some(hours) = hours_to_first_subscription {
+            time_to_event_stats.to_first_subscription_mut().add(hours);
+        }
+        if let Some(hours) = hours_to_first_cancel {
+            time_to_event_stats.to_first_cancel_mut().add(hours);
+        }
+        if let Some(hours) = hours_to_uninstall {
+            time_to_event_stats.to_uninstall_mut().add(hours);
+        }
+
+        merchant.set_hours_to_first_subscription(hours_to_first_subscription);
+        merchant.set_hours_to_first_cancel(hours_to_first_cancel);
+        merchant.set_hours_to_uninstall(hours_to_uninstall);
+    }
+
+    total_stats
+        .time_to_event_stats_mut()
+        .calculate_percentiles();
+}
+
+/// Analyze store closures as a lifecycle apart from app installs.
+/// A store is closed at start if its first closure-related event is a reopen.
+fn analyze_store_lifecycle(total_stats: &mut TotalStats, merchant_list: &mut MerchantList) {
+    let (Some(data_start_time), Some(data_end_time)) =
+        (*merchant_list.start_time(), *merchant_list.end_time())
+    else {
+        return;
+    };
+
+    let mut closed_merchant_count: u32 = 0;
+    let mut total_days_closed: f64 = 0.0;
+
+    for merchant in merchant_list.merchants_mut().values_mut() {
+        let mut store_events: Vec<&AppEvent> = merchant
+            .store_events()
+            .iter()
+            .filter(|e| e.time().is_some())
+            .collect();
+        store_events.sort_by_key(|e| *e.time());
+
+        let closed_at_start = if *merchant.carried_over() {
+            *merchant.store_closed_at_start()
+        } else {
+            store_events
+                .first()
+                .is_some_and(|e| e.is(EventCategory::StoreReopened))
+        };
+
+        //  Replay closed intervals within the period
+        let mut closed_since: Option<NaiveDateTime> = closed_at_start.then_some(data_start_time);
+        let mut days_closed: f64 = 0.0;
+        for event in store_events.iter() {
+            let time = event.time().unwrap();
+            if event.is(EventCategory::StoreClosed) {
+                closed_since.get_or_insert(time);
+            } else if let Some(since) = closed_since.take() {
+                days_closed += (time - since).num_seconds() as f64 / 86400.0;
+            }
+        }
+        let closed_at_end = closed_since.is_some();
+        if let Some(since) = closed_since {
+            days_closed += (data_end_time - since).num_seconds().max(0) as f64 / 86400.0;
+        }
+
+        let store_status = match store_events.last() {
+            Some(e) if e.is(EventCategory::StoreClosed) => STORE_STATUS_CLOSED,
+            Some(_) => STORE_STATUS_REOPENED,
+            None if closed_at_start => STORE_STATUS_CLOSED,
+            None => STORE_STATUS_OPEN,
+        };
+
+        merchant.set_store_status(store_status.to_string());
+        merchant.set_store_closed_at_start(closed_at_start);
+        merchant.set_store_closed_at_end(closed_at_end);
+        merchant.set_days_closed(days_closed);
+
+        if closed_at_end {
+            total_stats.increase_closed_store_count(1);
+        }
+        if days_closed > 0.0 {
+            closed_merchant_count += 1;
+            total_days_closed += days_closed;
+        }
+    }
+
+    total_stats.set_average_days_closed(if closed_merchant_count > 0 {
+        total_days_closed / closed_merchant_count as f64
+    } else {
+        0.0
+    });
+}
+
+/// Analyze churn against the active base at period start.
+/// Merchants are active at start if their first install-related event is an uninstall, or if they
+/// have events without any install-related one. Subscriptions exist at start if canceled before
+/// (or at the same time as) any activation. Merchants with a closed store are not active, their
+/// churn is counted apart from uninstalls.
+fn analyze_churn(
+    total_stats: &mut TotalStats,
+    merchant_list: &mut MerchantList,
+    pricing_defs: &PricingDefs,
+    case_sensitive_regex: bool,
+) {
+    for merchant in merchant_list.merchants_mut().values_mut() {
+        //  Installed state at start and end
+        let mut installing_events: Vec<&AppEvent> = merchant
+            .installing_events()
+            .iter()
+            .filter(|e| e.is(EventCategory::Installed) || e.is(EventCategory::Uninstalled))
+            .collect();
+        installing_events.sort_by_key(|e| *e.time());
+
+        //  Without opening state, a merchant is guessed to be installed before if its first installing event is not an install
+        let active_at_start = if *merchant.carried_over() {
+            *merchant.active_at_start()
+        } else {
+            installing_events
+                .first()
+                .is_none_or(|e| e.is(EventCategory::Uninstalled))
+        };
+        let active_at_end = installing_events
+            .last()
+            .map_or(active_at_start, |e| e.is(EventCategory::Installed));
+
+        //  Subscription at start and end
+        let mut subscription_events: Vec<&AppEvent> = merchant
+            .subscription_events()
+            .iter()
+            .filter(|e| e.time().is_some())
+            .collect();
+        subscription_events.sort_by_key(|e| *e.time());
+
+        let find_plan = |event: &AppEvent| {
+            find_pricing_unit(
+                event.details(),
+                pricing_defs.subscriptions(),
+                case_sensitive_regex,
+            )
+            .map(|plan| {
+                (
+                    plan.clone(),
+                    find_billing_cycle(event.details(), case_sensitive_regex),
+                )
+            })
+        };
+
+        let first_activated_time = subscription_events
+            .iter()
+            .find(|e| e.is(EventCategory::SubscriptionActivated))
+            .and_then(|e| *e.time());
+        //  Canceling, freezing or unfreezing before any activation means subscribed at start
+        let sub_at_start = if *merchant.carried_over() {
+            merchant
+                .sub_plan_at_start()
+                .clone()
+                .zip(merchant.sub_billing_cycle_at_start().clone())
+        } else {
+            subscription_events
+                .iter()
+                .find(|e| {
+                    e.is(EventCategory::SubscriptionCanceled)
+                        || e.is(EventCategory::SubscriptionFrozen)
+                        || e.is(EventCategory::SubscriptionUnfrozen)
+                })
+                .filter(|e| first_activated_time.is_none_or(|t| e.time().unwrap() <= t))
+                .and_then(|e| find_plan(e))
+        };
+
+        //  A frozen subscription is kept, only a cancel ends it
+        let sub_at_end = if !active_at_end {
+            None
+        } else {
+            match subscription_events.iter().rev().find(|e| {
+                e.is(EventCategory::SubscriptionActivated)
+                    || e.is(EventCategory::SubscriptionCanceled)
+            }) {
+                Some(e) if e.is(EventCategory::SubscriptionActivated) => find_plan(e),
+                Some(_) => None,
+                None => sub_at_start.clone(),
+            }
+        };
+
+        merchant.set_active_at_start(active_at_start);
+        merchant.set_active_at_end(active_at_end);
+        merchant.set_sub_plan_at_start(sub_at_start.as_ref().map(|(p, _)| p.clone()));
+        merchant.set_sub_billing_cycle_at_start(sub_at_start.map(|(_, c)| c));
+        merchant.set_sub_plan_at_end(sub_at_end.as_ref().map(|(p, _)| p.clone()));
+        merchant.set_sub_billing_cycle_at_end(sub_at_end.map(|(_, c)| c));
+
+        total_stats.churn_stats_mut().add_merchant(merchant);
+    }
+
+    total_stats.churn_stats_mut().calculate_rates();
+}
+
+/// Analyze event list and return final data.
+/// The period is from the first to the last event, unless given.
+fn analyze_events_list(
+    event_list: &Vec<AppEvent>,
+    start_time: Option<NaiveDateTime>,
+    end_time: Option<NaiveDateTime>,
+    opening_state: Option<&MerchantList>,
+    settings: &AnalysisSettings,
+) -> anyhow::Result<(TotalStats, MerchantList)> {
+    let (Some(first_event), Some(last_event)) = (event_list.first(), event_list.last()) else {
+        return Err(anyhow!(
+            "{} {}!",
+            data::APP_EVENTS,
+            message::error::EMPTY_DATA
+        ));
+    };
+
+    analyze_events_in_period(
+        event_list,
+        start_time.or(*first_event.time()),
+        end_time.or(*last_event.time()),
+        opening_state,
+        settings,
+    )
+}
+
+/// Analyze event list within the given period and return final data.
+/// The opening state is the merchant data of a previous period, used as the active base at period start.
+fn analyze_events_in_period(
+    event_list: &Vec<AppEvent>,
+    start_time: Option<NaiveDateTime>,
+    end_time: Option<NaiveDateTime>,
+    opening_state: Option<&MerchantList>,
+    settings: &AnalysisSettings,
+) -> anyhow::Result<(TotalStats, MerchantList)> {
+    let pricing_defs = settings.pricing_defs();
+    let case_sensitive_regex = settings.case_sensitive_regex();
+
+    //  Event files may list the newest event first
+    let (start_time, end_time) = match (start_time, end_time) {
+        (Some(start), Some(end)) if start > end => (Some(end), Some(start)),
+        period => period,
+    };
+
+    let (mut total_stats, mut merchant_data) = build_base_data(
+        event_list,
+        start_time,
+        end_time,
+        opening_state,
+        pricing_defs,
+        settings.excluding_defs(),
+        case_sensitive_regex,
+    );
+
+    analyze_details(
+        &mut total_stats,
+        &mut merchant_data,
+        pricing_defs,
+        case_sensitive_regex,
+    );
+
+    analyze_store_lifecycle(&mut total_stats, &mut merchant_data);
+
+    analyze_churn(
+        &mut total_stats,
+        &mut merchant_data,
+        pricing_defs,
+        case_sensitive_regex,
+    );
+
+    analyze_trials(
+        &mut total_stats,
+        &mut merchant_data,
+        pricing_defs,
+        case_sensitive_regex,
+    );
+
+    analyze_reactivations(
+        &mut total_stats,
+        &mut merchant_data,
+        pricing_defs,
+        case_sensitive_regex,
+    );
+
+    analyze_time_to_events(&mut total_stats, &mut merchant_data);
+
+    analyze_one_times(
+        &mut total_stats,
+        &merchant_data,
+        pricing_defs,
+        case_sensitive_regex,
+    );
+
+    analyze_revenue(
+        &mut total_stats,
+        &mut merchant_data,
+        pricing_defs,
+        case_sensitive_regex,
+    );
+
+    total_stats.select_headline_churn(settings.churn_formula());
+
+    Ok((total_stats, merchant_data))
+}
+
+/// Group key of a merchant for the given dimension, determined from all events of the merchant.
+/// The shop name regex is the compiled pattern of a shop name dimension.
+fn merchant_group_key(
+    dimension: &GroupingDimension,
+    merchant_events: &[&AppEvent],
+    shop_name_regex: Option<&Regex>,
+    pricing_defs: &PricingDefs,
+    case_sensitive_regex: bool,
+) -> String {
+    //  Some events (e.g. uninstalled) may come without shop details, so use the first known value
+    let first_value = |get: fn(&AppEvent) -> String| -> Option<String> {
+        merchant_events
+            .iter()
+            .map(|e| get(e))
+            .find(|v| !v.is_empty())
+    };
+
+    let key = match dimension {
+        GroupingDimension::Field(field) => merchant_events
+            .iter()
+            .map(|e| e.field_value(field))
+            .find(|v| !v.is_empty()),
+
+        GroupingDimension::EmailDomain => first_value(|e| e.shop_email().clone())
+            .and_then(|email| email.rsplit_once('@').map(|(_, d)| d.to_lowercase())),
+
+        GroupingDimension::ShopNamePattern(_) => shop_name_regex.and_then(|re| {
+            first_value(|e| e.shop_name().clone()).and_then(|name| {
+                re.captures(&name).map(|c| {
+                    c.get(1)
+                        .or(c.get(0))
+                        .map(|m| m.as_str().to_string())
+                        .unwrap_or_default()
+                })
+            })
+        }),
+
+        GroupingDimension::PlanAtInstall => merchant_events
+            .iter()
+            .position(|e| e.is(EventCategory::Installed))
+            .and_then(|i| {
+                merchant_events[i..]
+                    .iter()
+                    .find(|e| e.is(EventCategory::SubscriptionActivated))
+            })
+            .and_then(|e| {
+                find_pricing_unit(
+                    e.details(),
+                    pricing_defs.subscriptions(),
+                    case_sensitive_regex,
+                )
+            })
+            .map(|plan| plan.code().clone()),
+
+        GroupingDimension::EverSubscribed => Some(
+            if merchant_events
+                .iter()
+                .any(|e| e.is(EventCategory::SubscriptionActivated))
+            {
+                YES.to_string()
+            } else {
+                NO.to_string()
+            },
+        ),
+    };
+
+    key.unwrap_or(NONE.to_string())
+}
+
+/// Break down all stats by the first dimension, then each group by the next dimensions.
+/// Each group is analyzed with the same logic and period as the whole event list.
+/// Only merchants with events in the period can be grouped, so the opening state is limited to them.
+fn analyze_breakdown(
+    event_list: &Vec<AppEvent>,
+    dimensions: &[GroupingDimension],
+    total_stats: &TotalStats,
+    opening_state: Option<&MerchantList>,
+    settings: &AnalysisSettings,
+) -> anyhow::Result<Option<BreakdownStats>> {
+    let Some((dimension, next_dimensions)) = dimensions.split_first() else {
+        return Ok(None);
+    };
+
+    let mut merchant_events: IndexMap<&str, Vec<&AppEvent>> = IndexMap::new();
+    for event in event_list {
+        merchant_events
+            .entry(event.shop_domain().as_str())
+            .or_default()
+            .push(event);
+    }
+
+    //  Compiled once for all merchants
+    let shop_name_regex: Option<Regex> = match dimension {
+        GroupingDimension::ShopNamePattern(pattern) if settings.case_sensitive_regex() => {
+            Some(Regex::new(pattern)?)
+        }
+        GroupingDimension::ShopNamePattern(pattern) => {
+            Some(Regex::new(format!("(?i){}", pattern).as_str())?)
+        }
+        _ => None,
+    };
+
+    let mut merchant_keys: IndexMap<&str, String> = IndexMap::new();
+    for (shop_domain, events) in merchant_events.iter() {
+        merchant_keys.insert(
+            shop_domain,
+            merchant_group_key(
+                dimension,
+                events,
+                shop_name_regex.as_ref(),
+                settings.pricing_defs(),
+                settings.case_sensitive_regex(),
+            ),
+        );
+    }
+
+    //  Carried-over merchants without events in the period are grouped by their earlier events
+    if let Some(opening_state) = opening_state {
+        let excluding_re =
+            excluding_regex(settings.excluding_defs(), settings.case_sensitive_regex());
+        for (shop_domain, merchant) in opening_state.merchants() {
+            if merchant_keys.contains_key(shop_domain.as_str())
+                || !merchant.ends_active()
+                || is_excluded_merchant(merchant, &excluding_re, settings.case_sensitive_regex())
+            {
+                continue;
+            }
+            let earlier_events: Vec<&AppEvent> = merchant.events().collect();
+            merchant_keys.insert(
+                shop_domain,
+                merchant_group_key(
+                    dimension,
+                    &earlier_events,
+                    shop_name_regex.as_ref(),
+                    settings.pricing_defs(),
+                    settings.case_sensitive_regex(),
+                ),
+            );
+        }
+    }
+
+    let mut grouped_shops: IndexMap<String, Vec<String>> = IndexMap::new();
+    for (shop_domain, key) in merchant_keys.iter() {
+        grouped_shops
+            .entry(key.clone())
+            .or_default()
+            .push(shop_domain.to_string());
+    }
+    grouped_shops.sort_keys();
+
+    let mut grouped_events: IndexMap<String, Vec<AppEvent>> = IndexMap::new();
+    for event in event_list {
+        grouped_events
+            .entry(merchant_keys[event.shop_domain().as_str()].clone())
+            .or_default()
+            .push(event.clone());
+    }
+
+    let mut breakdown_stats = BreakdownStats::new(dimension.to_string());
+    for (group, shop_domains) in grouped_shops {
+        let events = grouped_events.shift_remove(&group).unwrap_or_default();
+        let group_opening_state = opening_state.map(|state| state.subset(shop_domains.iter()));
+
+        let (group_stats, _) = analyze_events_in_period(
+            &events,
+            *total_stats.start_time(),
+            *total_stats.end_time(),
+            group_opening_state.as_ref(),
+            settings,
+        )?;
+
+        let group_breakdown = analyze_breakdown(
+            &events,
+            next_dimensions,
+            total_stats,
+            group_opening_state.as_ref(),
+            settings,
+        )?;
+
+        breakdown_stats.insert_group(group, group_stats, group_breakdown);
+    }
+
+    Ok(Some(breakdown_stats))
+}
+
+/// Compare total stats of two periods.
+/// Numeric fields of either period are compared, a field missing in a period counts as 0.
+pub fn compare_total_stats(
+    base_stats: &TotalStats,
+    current_stats: &TotalStats,
+) -> anyhow::Result<StatsComparison> {
+    let numeric_values = |stats: &TotalStats| -> anyhow::Result<IndexMap<String, f64>> {
+        Ok(flatten_to_pairs(stats)?
+            .into_iter()
+            .filter(|(key, _)| {
+                !COMPARISON_SKIPPED_FIELDS
+                    .iter()
+                    .any(|field| key.starts_with(field))
+            })
+            .filter_map(|(key, value)| value.parse::<f64>().ok().map(|v| (key, v)))
+            .collect())
+    };
+
+    let base_values = numeric_values(base_stats)?;
+    let current_values = numeric_values(current_stats)?;
+
+    let mut stats_comparison = StatsComparison::new(base_stats, current_stats);
+    for key in base_values.keys().chain(current_values.keys()) {
+        if stats_comparison.metrics().contains_key(key) {
+            continue;
+        }
+        stats_comparison.metrics_mut().insert(
+            key.clone(),
+            MetricChange::new(
+                *base_values.get(key).unwrap_or(&0.0),
+                *current_values.get(key).unwrap_or(&0.0),
+            ),
+        );
+    }
+
+    Ok(stats_comparison)
+}
+
+/// Analyze events of a CSV export, after importing them to the history database of the given app if any
+fn analyze_file(
+    event_history_file: &PathBuf,
+    history_db: Option<(&Connection, &str)>,
+    opening_state: Option<&MerchantList>,
+    input_profile: &InputProfile,
+    event_vocabulary: &EventVocabulary,
+    settings: &AnalysisSettings,
+    outputs: &OutputOptions,
+) -> anyhow::Result<String> {
+    let mut event_list: Vec<AppEvent> = read_events_from_file(
+        event_history_file,
+        settings.excluding_defs().excluding_field(),
+        input_profile,
+    )?;
+    event_vocabulary.classify_events(&mut event_list, settings.case_sensitive_regex())?;
+
+    let mut message_success = String::new();
+    if let Some((conn, app)) = history_db {
+        write_events_to_history_db(conn, app, &event_list)?;
+        message_success +=
+            format!("{} {}\n", message::success::EVENTS_IMPORTED_HISTORY, app).as_str();
+    }
+
+    message_success += analyze_events_and_write(
+        &event_list,
+        None,
+        None,
+        opening_state,
+        settings,
+        outputs,
+        None,
+    )?
+    .as_str();
+
+    Ok(message_success)
+}
+
+/// Analyze event list and write all chosen outputs, return the success message.
+/// JSON is written whether chosen or not, the stats comparison is written when a base is given
+fn analyze_events_and_write(
+    event_list: &Vec<AppEvent>,
+    start_time: Option<NaiveDateTime>,
+    end_time: Option<NaiveDateTime>,
+    opening_state: Option<&MerchantList>,
+    settings: &AnalysisSettings,
+    outputs: &OutputOptions,
+    comparison_base: Option<&TotalStats>,
+) -> anyhow::Result<String> {
+    let (total_stats, merchant_data) =
+        analyze_events_list(event_list, start_time, end_time, opening_state, settings)?;
+    let breakdown_stats: Option<BreakdownStats> = analyze_breakdown(
+        event_list,
+        outputs.breakdown_dimensions(),
+        &total_stats,
+        opening_state,
+        settings,
+    )?;
+
+    //  Raw events are given to writers when they are written in debug mode
+    let output_context = OutputContext::new(
+        &total_stats,
+        &merchant_data,
+        outputs.debug_mode().then_some(event_list),
+        breakdown_stats.as_ref(),
+        outputs.report_template(),
+        comparison_base,
+    );
+
+    for output_format in outputs.output_formats() {
+        output_writer(output_format).ok_or(anyhow!(
+            "{}: {}",
+            message::error::INVALID_OUTPUT_FORMAT,
+            output_format
+        ))?;
+    }
+
+    //  Writers run in registering order, the chosen ones and those written on every run
+    let mut message_success: String = String::new();
+    for writer in output_writers()
+        .into_iter()
+        .filter(|w| w.always_written() || outputs.output_formats().iter().any(|f| f == w.name()))
+    {
+        message_success += writer
+            .write(outputs.output_naming(), &output_context)?
+            .as_str();
+    }
+
+    Ok(message_success)
+}
+
+pub fn analyze_from_gui(request: &AnalysisRequest) -> anyhow::Result<String> {
+    let AnalysisRequest {
+        event_history_file_list,
+        selected_pricing_defs_option,
+        pricing_defs_file,
+        selected_excluding_defs_option,
+        excluding_defs_file,
+        selected_input_profile_option,
+        input_profile_file,
+        selected_event_vocabulary_option,
+        event_vocabulary_file,
+        selected_report_template_option,
+        report_template_file,
+        opening_state_file,
+        out_folder,
+        out_file_name_template,
+        overwrite_policy,
+        history_app,
+        history_period,
+        comparison_period,
+        debug_mode,
+        output_formats,
+        case_sensitive_regex,
+        breakdown_dimensions,
+        churn_formula,
+    } = *request;
+
+    let pricing_defs: PricingDefs;
+    let excluding_defs: ExcludingDef;
+
+    pricing_defs = match selected_pricing_defs_option {
+        d if d.value() == ui::OPTION_CUSTOM.value() => {
+            if let Some(f) = pricing_defs_file {
+                read_pricing_def_from_json(&f)?
+            } else {
+                return Err(anyhow!(
+                    "{} {} {}!",
+                    data::KIND_CUSTOM.to_string(),
+                    data::PRICING_DEFS,
+                    message::error::FILE_NOT_CHOSEN
+                ));
+            }
+        }
+        _ => read_pricing_def_from_json_str(
+            selected_pricing_defs_option
+                .connected_data()
+                .as_ref()
+                .unwrap(),
+        )?,
+    };
+
+    excluding_defs = match selected_excluding_defs_option {
+        d if d.value() == ui::OPTION_CUSTOM.value() => {
+            if let Some(f) = excluding_defs_file {
+                read_excluding_def_from_json(&f)?
+            } else {
+                return Err(anyhow!(
+                    "{} {} {}!",
+                    data::KIND_CUSTOM.to_string(),
+                    data::EXCLUDING_DEFS,
+                    message::error::FILE_NOT_CHOSEN
+                ));
+            }
+        }
+        _ => read_excluding_def_from_json_str(
+            selected_excluding_defs_option
+                .connected_data()
+                .as_ref()
+                .unwrap(),
+        )?,
+    };
+
+    let input_profile: InputProfile = match selected_input_profile_option {
+        d if d.value() == ui::OPTION_CUSTOM.value() => {
+            if let Some(f) = input_profile_file {
+                read_input_profile_from_json(f)?
+            } else {
+                return Err(anyhow!(
+                    "{} {} {}!",
+                    data::KIND_CUSTOM,
+                    data::INPUT_PROFILE,
+                    message::error::FILE_NOT_CHOSEN
+                ));
+            }
+        }
+        _ => read_input_profile_from_json_str(
+            selected_input_profile_option
+                .connected_data()
+                .as_ref()
+                .unwrap(),
+        )?,
+    };
+
+    let event_vocabulary: EventVocabulary = match selected_event_vocabulary_option {
+        d if d.value() == ui::OPTION_CUSTOM.value() => {
+            if let Some(f) = event_vocabulary_file {
+                read_event_vocabulary_from_json(f)?
+            } else {
+                return Err(anyhow!(
+                    "{} {} {}!",
+                    data::KIND_CUSTOM,
+                    data::EVENT_VOCABULARY,
+                    message::error::FILE_NOT_CHOSEN
+                ));
+            }
+        }
+        _ => read_event_vocabulary_from_json_str(
+            selected_event_vocabulary_option
+                .connected_data()
+                .as_ref()
+                .unwrap(),
+        )?,
+    };
+
+    //  Template name is the bundled option value or the custom file name, None option has no template
+    let report_template: Option<(String, String)> = match selected_report_template_option {
+        d if d.value() == ui::OPTION_CUSTOM.value() => {
+            if let Some(f) = report_template_file {
+                Some((
+                    f.file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string(),
+                    read_report_template(f)?,
+                ))
+            } else {
+                return Err(anyhow!(
+                    "{} {} {}!",
+                    data::KIND_CUSTOM,
+                    data::REPORT_TEMPLATE,
+                    message::error::FILE_NOT_CHOSEN
+                ));
+            }
+        }
+        d => d
+            .connected_data()
+            .as_ref()
+            .map(|t| (d.value().to_string(), t.to_string())),
+    };
+
+    let opening_state: Option<MerchantList> = match opening_state_file {
+        Some(f) => Some(read_merchant_data_from_json(f)?),
+        None => None,
+    };
+
+    let history_db: Option<Connection> = if history_app.is_empty() {
+        None
+    } else {
+        Some(open_history_db(
+            &std::env::current_dir()?.join(data::HISTORY_DB_FILE_NAME),
+        )?)
+    };
+
+    if history_period.is_none() && event_history_file_list.is_none() {
+        return Err(anyhow!(
+            "{} {}!",
+            data::APP_EVENTS,
+            message::error::FILE_NOT_CHOSEN
+        ));
+    }
+
+    //  App name of file names is the history app, or the pricing definitions preset or file name
+    let app_name: String = if !history_app.is_empty() {
+        history_app.to_string()
+    } else if selected_pricing_defs_option.value() == ui::OPTION_CUSTOM.value() {
+        pricing_defs_file
+            .as_ref()
+            .and_then(|f| f.file_stem())
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string()
+    } else {
+        selected_pricing_defs_option.value().to_string()
+    };
+    let output_naming: OutputNaming = build_output_naming(
+        out_folder,
+        out_file_name_template,
+        &app_name,
+        overwrite_policy,
+    )?;
+
+    let mut final_error_message: String = String::from("");
+    let mut final_success_message: String = String::new();
+
+    //  Unknown formats are rejected before any file is written
+    for output_format in output_formats {
+        output_writer(output_format).ok_or(anyhow!(
+            "{}: {}",
+            message::error::INVALID_OUTPUT_FORMAT,
+            output_format
+        ))?;
+    }
+
+    let breakdown_dimensions: Vec<GroupingDimension> =
+        GroupingDimension::parse_list(breakdown_dimensions)?;
+
+    let settings = AnalysisSettings::new(
+        &pricing_defs,
+        &excluding_defs,
+        case_sensitive_regex,
+        churn_formula,
+    );
+    let outputs = OutputOptions::new(
+        &output_naming,
+        output_formats,
+        report_template
+            .as_ref()
+            .map(|(name, source)| (name.as_str(), source.as_str())),
+        &breakdown_dimensions,
+        debug_mode,
+    );
+
+    //  Analyze the history database over the period instead of the chosen files
+    if let Some((start_date, end_date)) = history_period {
+        let Some(conn) = &history_db else {
+            return Err(anyhow!(
+                "{} {}!",
+                data::HISTORY_APP,
+                message::error::EMPTY_DATA
+            ));
+        };
+
+        let start_time = parse_history_date(start_date)?.and_then(|d| d.and_hms_opt(0, 0, 0));
+        let end_time = parse_history_date(end_date)?.and_then(|d| d.and_hms_opt(23, 59, 59));
+
+        let mut event_list: Vec<AppEvent> = read_events_from_history_db(
+            conn,
+            history_app,
+            start_time,
+            end_time,
+            excluding_defs.excluding_field(),
+        )?;
+        event_vocabulary.classify_events(&mut event_list, case_sensitive_regex)?;
+
+        //  Base period of the comparison, analyzed from the same history
+        let mut comparison_base: Option<TotalStats> = None;
+
+        if let Some((base_start_date, base_end_date)) = comparison_period {
+            let base_start_time =
+                parse_history_date(base_start_date)?.and_then(|d| d.and_hms_opt(0, 0, 0));
+            let base_end_time =
+                parse_history_date(base_end_date)?.and_then(|d| d.and_hms_opt(23, 59, 59));
+
+            let mut base_event_list: Vec<AppEvent> = read_events_from_history_db(
+                conn,
+                history_app,
+                base_start_time,
+                base_end_time,
+                excluding_defs.excluding_field(),
+            )?;
+            event_vocabulary.classify_events(&mut base_event_list, case_sensitive_regex)?;
+
+            let (base_stats, _) = analyze_events_list(
+                &base_event_list,
+                base_start_time,
+                base_end_time,
+                None,
+                &settings,
+            )?;
+            comparison_base = Some(base_stats);
+        }
+
+        return analyze_events_and_write(
+            &event_list,
+            start_time,
+            end_time,
+            opening_state.as_ref(),
+            &settings,
+            &outputs,
+            comparison_base.as_ref(),
+        );
+    }
+
+    for f in event_history_file_list.iter().flatten() {
+        match analyze_file(
+            f,
+            history_db.as_ref().map(|conn| (conn, history_app)),
+            opening_state.as_ref(),
+            &input_profile,
+            &event_vocabulary,
+            &settings,
+            &outputs,
+        ) {
+            Ok(message_success) => final_success_message += message_success.as_str(),
+            Err(e) => final_error_message += e.to_string().as_str(),
+        }
+    }
+
+    if !final_error_message.is_empty() {
+        return Err(anyhow!(final_error_message));
+    }
+
+    Ok(final_success_message)
+}
+
+/// Compare two total stats files, the one starting earlier is the base
+pub fn compare_from_gui(
+    total_stats_file_list: &Option<Vec<PathBuf>>,
+    out_folder: &Option<PathBuf>,
+    out_file_name_template: &str,
+    overwrite_policy: &OverwritePolicy,
+) -> anyhow::Result<String> {
+    let Some([first_file, second_file]) = total_stats_file_list.as_deref() else {
+        return Err(anyhow!(
+            "{} {}!",
+            data::TOTAL_STATS,
+            message::error::COMPARISON_FILES_NOT_CHOSEN
+        ));
+    };
+
+    let first_stats = read_total_stats_from_json(first_file)?;
+    let second_stats = read_total_stats_from_json(second_file)?;
+
+    let (base_stats, current_stats) = if first_stats.start_time() <= second_stats.start_time() {
+        (first_stats, second_stats)
+    } else {
+        (second_stats, first_stats)
+    };
+
+    let output_naming: OutputNaming =
+        build_output_naming(out_folder, out_file_name_template, "", overwrite_policy)?;
+
+    //  Goes through the registered writer, so a replacing writer also applies here
+    let writer = output_writer(OUTPUT_FORMAT_STATS_COMPARISON).ok_or(anyhow!(
+        "{}: {}",
+        message::error::INVALID_OUTPUT_FORMAT,
+        OUTPUT_FORMAT_STATS_COMPARISON
+    ))?;
+    writer.write(
+        &output_naming,
+        &OutputContext::new(
+            &current_stats,
+            &MerchantList::new(),
+            None,
+            None,
+            None,
+            Some(&base_stats),
+        ),
+    )
+}
+
+/// Analyze event files with options of command line arguments, return the success message.
+/// Pricing and excluding definitions are preset names or files, default to the GUI defaults
+pub fn analyze_from_cli(args: &[String]) -> anyhow::Result<String> {
+    let mut event_history_file_list: Vec<PathBuf> = Vec::new();
+    let mut output_formats: Vec<String> = Vec::new();
+    let mut selected_pricing_defs_option: UiOption = ui::PRICING_DEFS_OPTION_SBM;
+    let mut pricing_defs_file: Option<PathBuf> = None;
+    let mut selected_excluding_defs_option: UiOption = ui::EXCLUDING_DEFS_OPTION_MS;
+    let mut excluding_defs_file: Option<PathBuf> = None;
+    let mut selected_input_profile_option: UiOption = ui::INPUT_PROFILE_OPTION_SHOPIFY;
+    let mut input_profile_file: Option<PathBuf> = None;
+    let mut selected_event_vocabulary_option: UiOption = ui::EVENT_VOCABULARY_OPTION_SHOPIFY;
+    let mut event_vocabulary_file: Option<PathBuf> = None;
+    let mut out_folder: Option<PathBuf> = None;
+
+    //  Preset of the given name, or custom with the given file
+    let select_option = |value: &str, option_list: &[UiOption]| match option_list
+        .iter()
+        .find(|o| o.value() == value)
+    {
+        Some(o) => (o.clone(), None),
+        None => (ui::OPTION_CUSTOM, Some(PathBuf::from(value))),
+    };
+
+    let mut args_iter = args.iter();
+    while let Some(arg) = args_iter.next() {
+        let mut option_value = || {
+            args_iter
+                .next()
+                .ok_or(anyhow!(message::error::CLI_ANALYZE_USAGE))
+        };
+        match arg.as_str() {
+            CLI_FORMAT_OPTION => {
+                output_formats = option_value()?
+                    .split(OUTPUT_FORMAT_SEPARATOR)
+                    .map(|f| f.trim().to_lowercase())
+                    .filter(|f| !f.is_empty())
+                    .collect();
+            }
+            CLI_PRICING_OPTION => {
+                (selected_pricing_defs_option, pricing_defs_file) =
+                    select_option(option_value()?, &ui::PRICING_DEFS_OPTION_LIST);
+            }
+            CLI_EXCLUDING_OPTION => {
+                (selected_excluding_defs_option, excluding_defs_file) =
+                    select_option(option_value()?, &ui::EXCLUDING_DEFS_OPTION_LIST);
+            }
+            CLI_PROFILE_OPTION => {
+                (selected_input_profile_option, input_profile_file) =
+                    select_option(option_value()?, &ui::INPUT_PROFILE_OPTION_LIST);
+            }
+            CLI_VOCABULARY_OPTION => {
+                (selected_event_vocabulary_option, event_vocabulary_file) =
+                    select_option(option_value()?, &ui::EVENT_VOCABULARY_OPTION_LIST);
+            }
+            CLI_OUT_FOLDER_OPTION => out_folder = Some(PathBuf::from(option_value()?)),
+            _ => event_history_file_list.push(PathBuf::from(arg)),
+        }
+    }
+
+    if event_history_file_list.is_empty() {
+        return Err(anyhow!(message::error::CLI_ANALYZE_USAGE));
+    }
+
+    analyze_from_gui(&AnalysisRequest {
+        event_history_file_list: &Some(event_history_file_list),
+        selected_pricing_defs_option: &selected_pricing_defs_option,
+        pricing_defs_file: &pricing_defs_file,
+        selected_excluding_defs_option: &selected_excluding_defs_option,
+        excluding_defs_file: &excluding_defs_file,
+        selected_input_profile_option: &selected_input_profile_option,
+        input_profile_file: &input_profile_file,
+        selected_event_vocabulary_option: &selected_event_vocabulary_option,
+        event_vocabulary_file: &event_vocabulary_file,
+        selected_report_template_option: &ui::REPORT_TEMPLATE_OPTION_NONE,
+        report_template_file: &None,
+        opening_state_file: &None,
+        out_folder: &out_folder,
+        out_file_name_template: "",
+        overwrite_policy: &OverwritePolicy::default(),
+        history_app: "",
+        history_period: None,
+        comparison_period: None,
+        debug_mode: false,
+        output_formats: &output_formats,
+        case_sensitive_regex: false,
+        breakdown_dimensions: "",
+        churn_formula: &ChurnFormula::default(),
+    })
+}
+
+/// Output naming scheme of a run, the output folder defaults to Output in the current folder
+fn build_output_naming(
+    out_folder: &Option<PathBuf>,
+    out_file_name_template: &str,
+    app_name: &str,
+    overwrite_policy: &OverwritePolicy,
+) -> anyhow::Result<OutputNaming> {
+    let out_folder: PathBuf = match out_folder {
+        Some(f) => f.clone(),
+        None => std::env::current_dir()?.join(data::OUT_FOLDER_NAME),
+    };
+
+    Ok(OutputNaming::new(
+        &out_folder,
+        out_file_name_template,
+        app_name,
+        overwrite_policy,
+    ))
+}
+
+/// Markdown summary of a total stats file,
+/// compared with the earlier one if two files are given
+pub fn summarize_from_files(total_stats_file_list: &[PathBuf]) -> anyhow::Result<String> {
+    let (current_stats, previous_stats) = match total_stats_file_list {
+        [file] => (read_total_stats_from_json(file)?, None),
+        [first_file, second_file] => {
+            let first_stats = read_total_stats_from_json(first_file)?;
+            let second_stats = read_total_stats_from_json(second_file)?;
+
+            if first_stats.start_time() <= second_stats.start_time() {
+                (second_stats, Some(first_stats))
+            } else {
+                (first_stats, Some(second_stats))
+            }
+        }
+        _ => {
+            return Err(anyhow!(
+                "{} {}!",
+                data::TOTAL_STATS,
+                message::error::SUMMARY_FILES_NOT_CHOSEN
+            ));
+        }
+    };
+
+    Ok(render_markdown_summary(
+        &current_stats,
+        previous_stats.as_ref(),
+    ))
+}
+
+/// Date of a history period bound, empty for no bound
+fn parse_history_date(date_str: &str) -> anyhow::Result<Option<NaiveDate>> {
+    if date_str.trim().is_empty() {
+        return Ok(None);
+    }
+
+    NaiveDate::parse_from_str(date_str.trim(), HISTORY_DATE_PATTERN)
+        .map(Some)
+        .map_err(|_| {
+            anyhow!(
+                "{}: {} ({})",
+                message::error::INVALID_DATE,
+                date_str,
+                HISTORY_DATE_PATTERN
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definitions::default_ms_excluding_def::MS_EXCLUDING_DEF_JSON_STRING;
+    use crate::definitions::default_ms_pricing_def::SBM_PRICING_DEF_JSON_STRING;
+
+    /// A free plan, a paid plan with a week of trial and a one-time pack
+    const TEST_PRICING_DEF_JSON_STRING: &str = r#"{
+        "subscriptions": [
+            { "code": "free", "name": "Free", "regex_pattern": "Free", "price": 0.0, "currency": "USD", "free": true },
+            { "code": "basic", "name": "Basic", "regex_pattern": "Basic", "price": 10.0, "currency": "USD", "trial_days": 7 }
+        ],
+        "one_times": [
+            { "code": "pack", "name": "Pack", "regex_pattern": "Pack", "price": 5.0, "currency": "USD" }
+        ]
+    }"#;
+
+    fn time(time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn event(shop_domain: &str, category: EventCategory, details: &str, at: &str) -> AppEvent {
+        let mut event = AppEvent::new();
+        event.set_shop_domain(shop_domain.to_string());
+        event.set_time(Some(time(at)));
+        event.set_details(details.to_string());
+        event.set_category(Some(category));
+        event
+    }
+
+    /// Analyze events over the given period with the test pricing definitions
+    fn analyze(events: &[AppEvent], start: &str, end: &str) -> (TotalStats, MerchantList) {
+        let pricing_defs = read_pricing_def_from_json_str(TEST_PRICING_DEF_JSON_STRING).unwrap();
+        let excluding_defs =
+            read_excluding_def_from_json_str(MS_EXCLUDING_DEF_JSON_STRING).unwrap();
+        let churn_formula = ChurnFormula::default();
+        let settings = AnalysisSettings::new(&pricing_defs, &excluding_defs, false, &churn_formula);
+
+        analyze_events_in_period(
+            &events.to_vec(),
+            Some(time(start)),
+            Some(time(end)),
+            None,
+            &settings,
+        )
+        .unwrap()
+    }
+
+    fn subscription_event(category: EventCategory, details: &str, hour: u32) -> AppEvent {
+        let mut event = AppEvent::new();
+        event.set_time(
+            NaiveDate::from_ymd_opt(2025, 1, 1)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0),
+        );
+        event.set_details(details.to_string());
+        event.set_category(Some(category));
+        event
+    }
+
+    #[test]
+    fn subscription_activated_then_frozen_counts_as_new() {
+        let pricing_defs = read_pricing_def_from_json_str(SBM_PRICING_DEF_JSON_STRING).unwrap();
+        let mut merchant_list = MerchantList::new();
+
+        //  Activated in the period and frozen afterwards
+        let mut frozen_new =
+            Merchant::new(&"new.myshopify.com".to_string(), pricing_defs.one_times());
+        frozen_new.increase_subscription_activated_count(1);
+        frozen_new.increase_subscription_frozen_count(1);
+        frozen_new.push_subscription_event(&subscription_event(
+            EventCategory::SubscriptionActivated,
+            "Standard",
+            1,
+        ));
+        frozen_new.push_subscription_event(&subscription_event(
+            EventCategory::SubscriptionFrozen,
+            "Standard",
+            2,
+        ));
+        merchant_list.update_merchant(frozen_new);
+
+        //  Only unfrozen in the period, the subscription started earlier
+        let mut unfrozen =
+            Merchant::new(&"old.myshopify.com".to_string(), pricing_defs.one_times());
+        unfrozen.increase_subscription_unfrozen_count(1);
+        unfrozen.push_subscription_event(&subscription_event(
+            EventCategory::SubscriptionUnfrozen,
+            "Standard",
+            1,
+        ));
+        merchant_list.update_merchant(unfrozen);
+
+        let mut total_stats = TotalStats::new(&pricing_defs);
+        analyze_details(&mut total_stats, &mut merchant_list, &pricing_defs, false);
+
+        let frozen_new = &merchant_list.merchants()["new.myshopify.com"];
+        assert_eq!(frozen_new.subscription_status(), SUBSCRIPTION_STATUS_FROZEN);
+        assert_eq!(
+            frozen_new
+                .last_new_sub_plan()
+                .as_ref()
+                .map(|plan| plan.code().as_str()),
+            Some("standard")
+        );
+        assert_eq!(*total_stats.new_sub_count(), 1);
+        assert_eq!(*total_stats.new_paying_sub_count(), 1);
+        assert_eq!(*total_stats.new_free_sub_count(), 0);
+        assert_eq!(
+            total_stats.sub_stats_details().new_sub().monthly_counts()["standard"],
+            1
+        );
+    }
+
+    #[test]
+    fn trials_are_told_apart_by_their_outcome() {
+        use EventCategory::*;
+        let (total_stats, merchant_list) = analyze(
+            &[
+                //  Still on the paid plan when the week of trial ends
+                event(
+                    "converted.myshopify.com",
+                    SubscriptionActivated,
+                    "Basic",
+                    "2025-01-01 00:00",
+                ),
+                //  Canceled during the trial
+                event(
+                    "expired.myshopify.com",
+                    SubscriptionActivated,
+                    "Basic",
+                    "2025-01-02 00:00",
+                ),
+                event(
+                    "expired.myshopify.com",
+                    SubscriptionCanceled,
+                    "Basic",
+                    "2025-01-04 00:00",
+                ),
+                //  Uninstalled during the trial
+                event(
+                    "uninstalled.myshopify.com",
+                    SubscriptionActivated,
+                    "Basic",
+                    "2025-01-03 00:00",
+                ),
+                event(
+                    "uninstalled.myshopify.com",
+                    Uninstalled,
+                    "",
+                    "2025-01-05 00:00",
+                ),
+                //  Trial ends after the period
+                event(
+                    "trialing.myshopify.com",
+                    SubscriptionActivated,
+                    "Basic",
+                    "2025-01-29 00:00",
+                ),
+                //  Free plan has no trial
+                event(
+                    "free.myshopify.com",
+                    SubscriptionActivated,
+                    "Free",
+                    "2025-01-10 00:00",
+                ),
+            ],
+            "2025-01-01 00:00",
+            "2025-01-31 00:00",
+        );
+
+        let trial_stats = total_stats.trial_stats();
+        assert_eq!(*trial_stats.started_count(), 4);
+        assert_eq!(*trial_stats.converted_count(), 1);
+        assert_eq!(*trial_stats.expired_count(), 1);
+        assert_eq!(*trial_stats.uninstalled_count(), 1);
+        assert_eq!(*trial_stats.ongoing_count(), 1);
+        assert!((trial_stats.conversion_rate() - 100.0 / 3.0).abs() < 1e-9);
+        assert_eq!(*trial_stats.median_days_to_conversion(), 7.0);
+
+        let trial_status = |shop_domain: &str| {
+            merchant_list.merchants()[shop_domain]
+                .trial_status()
+                .clone()
+        };
+        assert_eq!(
+            trial_status("converted.myshopify.com"),
+            TRIAL_STATUS_CONVERTED
+        );
+        assert_eq!(trial_status("expired.myshopify.com"), TRIAL_STATUS_EXPIRED);
+        assert_eq!(
+            trial_status("uninstalled.myshopify.com"),
+            TRIAL_STATUS_UNINSTALLED
+        );
+        assert_eq!(
+            trial_status("trialing.myshopify.com"),
+            TRIAL_STATUS_TRIALING
+        );
+        assert!(
+            merchant_list.merchants()["free.myshopify.com"]
+                .trial_plan()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn free_plans_are_kept_out_of_paid_growth() {
+        use EventCategory::*;
+        let (total_stats, _) = analyze(
+            &[
+                event(
+                    "paying.myshopify.com",
+                    SubscriptionActivated,
+                    "Basic",
+                    "2025-01-02 00:00",
+                ),
+                event(
+                    "free.myshopify.com",
+                    SubscriptionActivated,
+                    "Free",
+                    "2025-01-03 00:00",
+                ),
+                event(
+                    "canceled.myshopify.com",
+                    SubscriptionCanceled,
+                    "Basic",
+                    "2025-01-04 00:00",
+                ),
+                event(
+                    "free_canceled.myshopify.com",
+                    SubscriptionCanceled,
+                    "Free",
+                    "2025-01-05 00:00",
+                ),
+                event(
+                    "buyer.myshopify.com",
+                    OneTimeActivated,
+                    "Pack",
+                    "2025-01-06 00:00",
+                ),
+            ],
+            "2025-01-01 00:00",
+            "2025-01-31 00:00",
+        );
+
+        assert_eq!(*total_stats.new_sub_count(), 2);
+        assert_eq!(*total_stats.new_paying_sub_count(), 1);
+        assert_eq!(*total_stats.new_free_sub_count(), 1);
+        assert_eq!(*total_stats.canceled_paying_sub_count(), 1);
+        assert_eq!(*total_stats.canceled_free_sub_count(), 1);
+        assert_eq!(*total_stats.paying_sub_growth(), 0);
+        assert_eq!(*total_stats.free_sub_growth(), 0);
+        //  Only the one-time purchase grows paid numbers
+        assert_eq!(*total_stats.paid_growth(), 1);
+        assert_eq!(
+            total_stats
+                .paying_sub_stats_details()
+                .new_sub()
+                .monthly_counts()["basic"],
+            1
+        );
+        assert_eq!(
+            total_stats
+                .free_sub_stats_details()
+                .new_sub()
+                .monthly_counts()["free"],
+            1
+        );
+    }
+
+    #[test]
+    fn revenue_is_estimated_from_billed_cycles_after_the_trial() {
+        use EventCategory::*;
+        let (total_stats, merchant_list) = analyze(
+            &[
+                //  Billed from the end of the trial on Jan 8, 53 days make 2 monthly cycles
+                event(
+                    "subscriber.myshopify.com",
+                    Installed,
+                    "",
+                    "2025-01-01 00:00",
+                ),
+                event(
+                    "subscriber.myshopify.com",
+                    SubscriptionActivated,
+                    "Basic",
+                    "2025-01-01 00:00",
+                ),
+                event("leaver.myshopify.com", Installed, "", "2025-01-01 00:00"),
+                event("leaver.myshopify.com", Uninstalled, "", "2025-01-31 00:00"),
+                //  Installed before the period, its tenure starts with the period
+                event(
+                    "buyer.myshopify.com",
+                    OneTimeActivated,
+                    "Pack",
+                    "2025-01-10 00:00",
+                ),
+            ],
+            "2025-01-01 00:00",
+            "2025-03-02 00:00",
+        );
+
+        let merchant = |shop_domain: &str| &merchant_list.merchants()[shop_domain];
+        assert_eq!(
+            *merchant("subscriber.myshopify.com").subscription_revenue(),
+            20.0
+        );
+        assert_eq!(*merchant("subscriber.myshopify.com").tenure_days(), 60.0);
+        assert_eq!(*merchant("leaver.myshopify.com").lifetime_revenue(), 0.0);
+        assert_eq!(*merchant("leaver.myshopify.com").tenure_days(), 30.0);
+        assert_eq!(*merchant("buyer.myshopify.com").one_time_revenue(), 5.0);
+        assert_eq!(*merchant("buyer.myshopify.com").tenure_days(), 60.0);
+
+        let revenue_stats = total_stats.revenue_stats();
+        assert_eq!(*revenue_stats.total_revenue(), 25.0);
+        assert_eq!(*revenue_stats.paying_merchant_count(), 2);
+        assert!((revenue_stats.arpu() - 25.0 / 3.0).abs() < 1e-9);
+        assert_eq!(*revenue_stats.arppu(), 12.5);
+        assert_eq!(*revenue_stats.average_tenure_days(), 50.0);
+
+        //  Only merchants installed in the period form a cohort, 20 over 3 merchant-months with 1 uninstall
+        let cohort = &revenue_stats.cohorts()["2025-01"];
+        assert_eq!(*cohort.merchant_count(), 2);
+        assert_eq!(*cohort.uninstalled_count(), 1);
+        assert!((cohort.ltv_estimate() - 20.0).abs() < 1e-9);
+        assert!((revenue_stats.ltv_estimate() - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn one_time_purchases_tell_repeat_buyers_apart() {
+        use EventCategory::*;
+        let mut untimed_purchase = event(
+            "repeat.myshopify.com",
+            OneTimeActivated,
+            "Pack",
+            "2025-01-20 00:00",
+        );
+        untimed_purchase.set_time(None);
+        let (total_stats, _) = analyze(
+            &[
+                event(
+                    "repeat.myshopify.com",
+                    OneTimeActivated,
+                    "Pack",
+                    "2025-01-02 00:00",
+                ),
+                event(
+                    "once.myshopify.com",
+                    OneTimeActivated,
+                    "Pack",
+                    "2025-01-03 00:00",
+                ),
+                event(
+                    "repeat.myshopify.com",
+                    OneTimeActivated,
+                    "Pack",
+                    "2025-01-06 00:00",
+                ),
+                event(
+                    "repeat.myshopify.com",
+                    OneTimeActivated,
+                    "Pack",
+                    "2025-01-12 00:00",
+                ),
+                untimed_purchase,
+            ],
+            "2025-01-01 00:00",
+            "2025-01-31 00:00",
+        );
+
+        for pack_stats in [
+            total_stats.one_time_summary(),
+            &total_stats.one_time_pack_stats()["pack"],
+        ] {
+            assert_eq!(*pack_stats.purchase_count(), 4);
+            assert_eq!(*pack_stats.revenue(), 20.0);
+            assert_eq!(*pack_stats.buyer_count(), 2);
+            assert_eq!(*pack_stats.repeat_buyer_count(), 1);
+            assert_eq!(*pack_stats.average_packs_per_buyer(), 2.0);
+            //  Repeat purchases 4 and 6 days apart
+            assert_eq!(*pack_stats.average_days_between_purchases(), 5.0);
+            assert_eq!(*pack_stats.median_days_between_purchases(), 5.0);
+        }
+    }
+
+    #[test]
+    fn reinstalls_and_resubscriptions_are_reactivations() {
+        use EventCategory::*;
+        let (total_stats, merchant_list) = analyze(
+            &[
+                event(
+                    "reinstaller.myshopify.com",
+                    Installed,
+                    "",
+                    "2025-01-01 00:00",
+                ),
+                event(
+                    "reinstaller.myshopify.com",
+                    SubscriptionActivated,
+                    "Basic",
+                    "2025-01-01 01:00",
+                ),
+                event(
+                    "reinstaller.myshopify.com",
+                    Uninstalled,
+                    "",
+                    "2025-01-05 00:00",
+                ),
+                event(
+                    "reinstaller.myshopify.com",
+                    Installed,
+                    "",
+                    "2025-01-09 00:00",
+                ),
+                event(
+                    "reinstaller.myshopify.com",
+                    SubscriptionActivated,
+                    "Free",
+                    "2025-01-10 00:00",
+                ),
+                event(
+                    "resubscriber.myshopify.com",
+                    SubscriptionActivated,
+                    "Basic",
+                    "2025-01-02 00:00",
+                ),
+                event(
+                    "resubscriber.myshopify.com",
+                    SubscriptionCanceled,
+                    "Basic",
+                    "2025-01-03 00:00",
+                ),
+                event(
+                    "resubscriber.myshopify.com",
+                    SubscriptionActivated,
+                    "Basic",
+                    "2025-01-13 00:00",
+                ),
+                //  Switching plans within the minimum gap is not coming back
+                event(
+                    "switcher.myshopify.com",
+                    SubscriptionCanceled,
+                    "Basic",
+                    "2025-01-04 00:00",
+                ),
+                event(
+                    "switcher.myshopify.com",
+                    SubscriptionActivated,
+                    "Free",
+                    "2025-01-04 00:30",
+                ),
+            ],
+            "2025-01-01 00:00",
+            "2025-01-31 00:00",
+        );
+
+        let reinstall = &merchant_list.merchants()["reinstaller.myshopify.com"].reactivations()[0];
+        assert_eq!(reinstall.kind(), REACTIVATION_KIND_REINSTALL);
+        assert_eq!(*reinstall.gap_days(), 4.0);
+        assert_eq!(reinstall.previous_plan().as_deref(), Some("basic"));
+        assert_eq!(reinstall.returned_plan().as_deref(), Some("free"));
+        assert!(
+            merchant_list.merchants()["switcher.myshopify.com"]
+                .reactivations()
+                .is_empty()
+        );
+
+        let reactivation_stats = total_stats.reactivation_stats();
+        assert_eq!(*reactivation_stats.reinstalled_count(), 1);
+        assert_eq!(*reactivation_stats.reinstalled_merchant_count(), 1);
+        assert_eq!(*reactivation_stats.resubscribed_count(), 1);
+        assert_eq!(*reactivation_stats.resubscribed_merchant_count(), 1);
+        assert_eq!(*reactivation_stats.median_reinstall_gap_days(), 4.0);
+        assert_eq!(*reactivation_stats.median_resubscribe_gap_days(), 10.0);
+        assert_eq!(*reactivation_stats.same_plan_count(), 1);
+        assert_eq!(*reactivation_stats.different_plan_count(), 1);
+    }
+
+    #[test]
+    fn time_to_events_is_counted_from_first_install() {
+        use EventCategory::*;
+        let mut events: Vec<AppEvent> = Vec::new();
+        for (shop_domain, subscribed_at) in [
+            ("a.myshopify.com", "2025-01-01 00:30"),
+            ("b.myshopify.com", "2025-01-01 02:00"),
+            ("c.myshopify.com", "2025-01-01 10:00"),
+            ("d.myshopify.com", "2025-01-03 00:00"),
+        ] {
+            events.push(event(shop_domain, Installed, "", "2025-01-01 00:00"));
+            events.push(event(
+                shop_domain,
+                SubscriptionActivated,
+                "Basic",
+                subscribed_at,
+            ));
+        }
+        events.push(event(
+            "d.myshopify.com",
+            SubscriptionCanceled,
+            "Basic",
+            "2025-01-04 00:00",
+        ));
+        events.push(event(
+            "quick.myshopify.com",
+            Installed,
+            "",
+            "2025-01-01 00:00",
+        ));
+        events.push(event(
+            "quick.myshopify.com",
+            Uninstalled,
+            "",
+            "2025-01-01 00:30",
+        ));
+        //  Installed before the period, not counted
+        events.push(event(
+            "old.myshopify.com",
+            SubscriptionActivated,
+            "Basic",
+            "2025-01-02 00:00",
+        ));
+
+        let (total_stats, merchant_list) = analyze(&events, "2025-01-01 00:00", "2025-01-31 00:00");
+
+        let to_first_subscription = total_stats.time_to_event_stats().to_first_subscription();
+        assert_eq!(*to_first_subscription.count(), 4);
+        assert_eq!(*to_first_subscription.p25_hours(), 1.625);
+        assert_eq!(*to_first_subscription.p50_hours(), 6.0);
+        assert_eq!(*to_first_subscription.p75_hours(), 19.5);
+        assert!((to_first_subscription.p90_hours() - 36.6).abs() < 1e-9);
+        assert_eq!(to_first_subscription.histogram()["< 1 hour"], 1);
+        assert_eq!(to_first_subscription.histogram()["1-24 hours"], 2);
+        assert_eq!(to_first_subscription.histogram()["1-7 days"], 1);
+
+        let to_first_cancel = total_stats.time_to_event_stats().to_first_cancel();
+        assert_eq!(*to_first_cancel.count(), 1);
+        assert_eq!(*to_first_cancel.p50_hours(), 72.0);
+        assert_eq!(
+            *total_stats.time_to_event_stats().to_uninstall().p50_hours(),
+            0.5
+        );
+
+        let merchant = &merchant_list.merchants()["d.myshopify.com"];
+        assert_eq!(*merchant.hours_to_first_subscription(), Some(48.0));
+        assert_eq!(*merchant.hours_to_first_cancel(), Some(72.0));
+        assert_eq!(*merchant.hours_to_uninstall(), None);
+        assert_eq!(
+            *merchant_list.merchants()["old.myshopify.com"].hours_to_first_subscription(),
+            None
+        );
+    }
+
+    #[test]
+    fn churn_is_measured_against_the_starting_active_base() {
+        use EventCategory::*;
+        let (mut total_stats, _) = analyze(
+            &[
+                //  Active at start without any subscription
+                event(
+                    "loyal.myshopify.com",
+                    OneTimeActivated,
+                    "Pack",
+                    "2025-01-03 00:00",
+                ),
+                event("leaver.myshopify.com", Uninstalled, "", "2025-01-10 00:00"),
+                //  Subscribed at start: a downgrade, a cancel and a pause
+                event(
+                    "downgrader.myshopify.com",
+                    SubscriptionCanceled,
+                    "Basic",
+                    "2025-01-05 00:00",
+                ),
+                event(
+                    "downgrader.myshopify.com",
+                    SubscriptionActivated,
+                    "Free",
+                    "2025-01-05 00:30",
+                ),
+                event(
+                    "canceler.myshopify.com",
+                    SubscriptionCanceled,
+                    "Basic",
+                    "2025-01-15 00:00",
+                ),
+                event(
+                    "pauser.myshopify.com",
+                    SubscriptionFrozen,
+                    "Basic",
+                    "2025-01-10 00:00",
+                ),
+                //  Installed in the period, not in the starting base
+                event("newcomer.myshopify.com", Installed, "", "2025-01-02 00:00"),
+            ],
+            "2025-01-01 00:00",
+            "2025-01-31 00:00",
+        );
+
+        let churn_stats = total_stats.churn_stats();
+        assert_eq!(*churn_stats.starting_active_count(), 5);
+        assert_eq!(*churn_stats.ending_active_count(), 5);
+        assert_eq!(*churn_stats.churned_count(), 1);
+        assert_eq!(*churn_stats.churned_uninstalled_count(), 1);
+        assert_eq!(*churn_stats.logo_churn_rate(), 20.0);
+        assert_eq!(*churn_stats.starting_mrr(), 30.0);
+        assert_eq!(*churn_stats.churned_mrr(), 20.0);
+        assert!((churn_stats.revenue_churn_rate() - 200.0 / 3.0).abs() < 1e-9);
+
+        let basic_churn = &churn_stats.sub_churn()["basic"];
+        assert_eq!(*basic_churn.starting_count(), 3);
+        assert_eq!(*basic_churn.churned_count(), 2);
+
+        //  Uninstalls over installs by default
+        assert_eq!(*total_stats.headline_churn_rate(), 100.0);
+        total_stats.select_headline_churn(&ChurnFormula::Logo);
+        assert_eq!(*total_stats.headline_churn_rate(), 20.0);
     }
 }
@@ -2,20 +2,33 @@ use std::path::PathBuf;
 
 use eframe::{
     APP_KEY, App, CreationContext, NativeOptions, Storage,
-    egui::{Button, CentralPanel, ComboBox, Ui, ViewportBuilder, pos2},
+    egui::{Button, CentralPanel, ComboBox, TextEdit, Ui, ViewportBuilder, pos2},
     get_value, icon_data, run_native, set_value,
 };
 use rfd::FileDialog;
 use serde::{self, Deserialize, Serialize};
 
+use crate::definitions::common::DEFAULT_OUT_FILE_NAME_TEMPLATE;
+use crate::definitions::strings::message;
 use crate::definitions::strings::ui::*;
+use crate::models::data_model::{ChurnFormula, OverwritePolicy};
 use crate::models::ui_model::*;
-use crate::{analyzing::analyze_from_gui, definitions::strings::data::*};
+use crate::output_writers::output_writers;
+use crate::{
+    analyzing::{analyze_from_gui, compare_from_gui, summarize_from_files},
+    definitions::strings::data::*,
+};
 
 #[derive(Serialize, Deserialize)]
 struct QuickGUIApp {
     debug_mode: bool,
     case_sensitive_regex: bool,
+    #[serde(default)]
+    output_formats: Vec<String>,
+    #[serde(default)]
+    breakdown_dimensions: String,
+    #[serde(default)]
+    churn_formula: ChurnFormula,
 
     event_history_file_list: Option<Vec<PathBuf>>,
 
@@ -24,6 +37,54 @@ struct QuickGUIApp {
 
     selected_excluding_defs_option: UiOption,
     excluding_defs_file: Option<PathBuf>,
+
+    #[serde(default = "default_input_profile_option")]
+    selected_input_profile_option: UiOption,
+    #[serde(default)]
+    input_profile_file: Option<PathBuf>,
+
+    #[serde(default = "default_event_vocabulary_option")]
+    selected_event_vocabulary_option: UiOption,
+    #[serde(default)]
+    event_vocabulary_file: Option<PathBuf>,
+
+    #[serde(default = "default_report_template_option")]
+    selected_report_template_option: UiOption,
+    #[serde(default)]
+    report_template_file: Option<PathBuf>,
+
+    #[serde(default)]
+    opening_state_file: Option<PathBuf>,
+
+    #[serde(default)]
+    out_folder: Option<PathBuf>,
+    #[serde(default)]
+    out_file_name_template: String,
+    #[serde(default)]
+    overwrite_policy: OverwritePolicy,
+
+    #[serde(default)]
+    history_app: String,
+    #[serde(default)]
+    history_start_date: String,
+    #[serde(default)]
+    history_end_date: String,
+    #[serde(default)]
+    comparison_start_date: String,
+    #[serde(default)]
+    comparison_end_date: String,
+}
+
+fn default_input_profile_option() -> UiOption {
+    INPUT_PROFILE_OPTION_SHOPIFY
+}
+
+fn default_event_vocabulary_option() -> UiOption {
+    EVENT_VOCABULARY_OPTION_SHOPIFY
+}
+
+fn default_report_template_option() -> UiOption {
+    REPORT_TEMPLATE_OPTION_NONE
 }
 
 impl Default for QuickGUIApp {
@@ -31,11 +92,29 @@ impl Default for QuickGUIApp {
         Self {
             debug_mode: false,
             case_sensitive_regex: false,
+            output_formats: Vec::new(),
+            breakdown_dimensions: String::new(),
+            churn_formula: ChurnFormula::default(),
             event_history_file_list: None,
             selected_pricing_defs_option: PRICING_DEFS_OPTION_SBM,
             pricing_defs_file: None,
             selected_excluding_defs_option: EXCLUDING_DEFS_OPTION_MS,
             excluding_defs_file: None,
+            selected_input_profile_option: INPUT_PROFILE_OPTION_SHOPIFY,
+            input_profile_file: None,
+            selected_event_vocabulary_option: EVENT_VOCABULARY_OPTION_SHOPIFY,
+            event_vocabulary_file: None,
+            selected_report_template_option: REPORT_TEMPLATE_OPTION_NONE,
+            report_template_file: None,
+            opening_state_file: None,
+            out_folder: None,
+            out_file_name_template: String::new(),
+            overwrite_policy: OverwritePolicy::default(),
+            history_app: String::new(),
+            history_start_date: String::new(),
+            history_end_date: String::new(),
+            comparison_start_date: String::new(),
+            comparison_end_date: String::new(),
         }
     }
 }
@@ -55,6 +134,63 @@ impl QuickGUIApp {
         }
         Default::default()
     }
+
+    /// Analyze the chosen files, or the history database over the given period, then show the result
+    fn analyze(&self, history_period: Option<(&str, &str)>) {
+        let comparison_period = if self.comparison_start_date.trim().is_empty()
+            && self.comparison_end_date.trim().is_empty()
+        {
+            None
+        } else {
+            Some((
+                self.comparison_start_date.as_str(),
+                self.comparison_end_date.as_str(),
+            ))
+        };
+
+        show_result(analyze_from_gui(&AnalysisRequest {
+            event_history_file_list: &self.event_history_file_list,
+            selected_pricing_defs_option: &self.selected_pricing_defs_option,
+            pricing_defs_file: &self.pricing_defs_file,
+            selected_excluding_defs_option: &self.selected_excluding_defs_option,
+            excluding_defs_file: &self.excluding_defs_file,
+            selected_input_profile_option: &self.selected_input_profile_option,
+            input_profile_file: &self.input_profile_file,
+            selected_event_vocabulary_option: &self.selected_event_vocabulary_option,
+            event_vocabulary_file: &self.event_vocabulary_file,
+            selected_report_template_option: &self.selected_report_template_option,
+            report_template_file: &self.report_template_file,
+            opening_state_file: &self.opening_state_file,
+            out_folder: &self.out_folder,
+            out_file_name_template: &self.out_file_name_template,
+            overwrite_policy: &self.overwrite_policy,
+            history_app: self.history_app.trim(),
+            history_period,
+            comparison_period,
+            debug_mode: self.debug_mode,
+            output_formats: &self.output_formats,
+            case_sensitive_regex: self.case_sensitive_regex,
+            breakdown_dimensions: &self.breakdown_dimensions,
+            churn_formula: &self.churn_formula,
+        }));
+    }
+}
+
+fn show_result(result: anyhow::Result<String>) {
+    match result {
+        Ok(m) => {
+            rfd::MessageDialog::new()
+                .set_description(m)
+                .set_level(rfd::MessageLevel::Info)
+                .show();
+        }
+        Err(e) => {
+            rfd::MessageDialog::new()
+                .set_description(format!("{:?}", e))
+                .set_level(rfd::MessageLevel::Error)
+                .show();
+        }
+    }
 }
 
 impl App for QuickGUIApp {
@@ -85,6 +221,27 @@ impl App for QuickGUIApp {
                     &mut self.excluding_defs_file,
                 );
             });
+
+            //  Column names, date formats and event names of event history files
+            ui.horizontal(|ui| {
+                selector_with_file_support(
+                    ui,
+                    INPUT_PROFILE,
+                    SELECTOR_INPUT_PROFILE_ID,
+                    &INPUT_PROFILE_OPTION_LIST.to_vec(),
+                    &mut self.selected_input_profile_option,
+                    &mut self.input_profile_file,
+                );
+
+                selector_with_file_support(
+                    ui,
+                    EVENT_VOCABULARY,
+                    SELECTOR_EVENT_VOCABULARY_ID,
+                    &EVENT_VOCABULARY_OPTION_LIST.to_vec(),
+                    &mut self.selected_event_vocabulary_option,
+                    &mut self.event_vocabulary_file,
+                );
+            });
             ui.separator();
 
             ui.add_space(4.0);
@@ -92,33 +249,12 @@ impl App for QuickGUIApp {
             ui.horizontal(|ui| {
                 if ui.button(BTN_EVENT_FILE_PICKER_LBL).clicked() {
                     self.event_history_file_list = FileDialog::new()
-                        .add_filter("csv", &["csv", "CSV"])
+                        .add_filter("csv, Partner API json", &["csv", "CSV", "json", "JSON"])
                         .pick_files();
                 }
 
                 if ui.button(BTN_ANALYZE_LBL).clicked() {
-                    match analyze_from_gui(
-                        &self.event_history_file_list,
-                        &self.selected_pricing_defs_option,
-                        &self.selected_excluding_defs_option,
-                        &self.pricing_defs_file,
-                        &self.excluding_defs_file,
-                        self.debug_mode,
-                        self.case_sensitive_regex,
-                    ) {
-                        Ok(m) => {
-                            rfd::MessageDialog::new()
-                                .set_description(m)
-                                .set_level(rfd::MessageLevel::Info)
-                                .show();
-                        }
-                        Err(e) => {
-                            rfd::MessageDialog::new()
-                                .set_description(format!("{:?}", e))
-                                .set_level(rfd::MessageLevel::Error)
-                                .show();
-                        }
-                    }
+                    self.analyze(None);
                 }
             });
 
@@ -132,6 +268,195 @@ impl App for QuickGUIApp {
                     CHECKBOX_CASE_SENSITIVE_REGEX_LBL,
                 );
             });
+            ui.horizontal(|ui| {
+                ui.label(CHECKBOX_GROUP_EXPORT_LBL);
+                for writer in output_writers().into_iter().filter(|w| !w.always_written()) {
+                    let mut checked = self.output_formats.iter().any(|f| f == writer.name());
+                    if ui.checkbox(&mut checked, writer.label()).changed() {
+                        if checked {
+                            self.output_formats.push(writer.name().to_string());
+                        } else {
+                            self.output_formats.retain(|f| f != writer.name());
+                        }
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label(SELECTOR_REPORT_TEMPLATE_LBL);
+                ComboBox::from_id_salt(SELECTOR_REPORT_TEMPLATE_ID)
+                    .selected_text(self.selected_report_template_option.text().to_string())
+                    .show_ui(ui, |ui| {
+                        for option in REPORT_TEMPLATE_OPTION_LIST.iter().chain([&OPTION_CUSTOM]) {
+                            ui.selectable_value(
+                                &mut self.selected_report_template_option,
+                                option.clone(),
+                                option.text().to_string(),
+                            );
+                        }
+                    });
+
+                if self.selected_report_template_option == OPTION_CUSTOM {
+                    if ui.button(BTN_BROWSE_LBL).clicked() {
+                        self.report_template_file = FileDialog::new().pick_file();
+                    }
+                    if let Some(f) = &self.report_template_file {
+                        ui.label(f.file_name().unwrap().display().to_string());
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label(SELECTOR_CHURN_FORMULA_LBL);
+                ComboBox::from_id_salt(SELECTOR_CHURN_FORMULA_ID)
+                    .selected_text(self.churn_formula.to_string())
+                    .show_ui(ui, |ui| {
+                        for formula in ChurnFormula::ALL {
+                            let text = formula.to_string();
+                            ui.selectable_value(&mut self.churn_formula, formula, text);
+                        }
+                    });
+            });
+            ui.horizontal(|ui| {
+                ui.label(INPUT_BREAKDOWN_DIMENSIONS_LBL);
+                ui.add(
+                    TextEdit::singleline(&mut self.breakdown_dimensions)
+                        .hint_text(INPUT_BREAKDOWN_DIMENSIONS_HINT),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label(INPUT_OPENING_STATE_LBL);
+                if ui.button(BTN_BROWSE_LBL).clicked()
+                    && let Some(f) = FileDialog::new()
+                        .add_filter("json", &["json", "JSON"])
+                        .pick_file()
+                {
+                    self.opening_state_file = Some(f);
+                }
+                if ui
+                    .add_enabled(
+                        self.opening_state_file.is_some(),
+                        Button::new(BTN_CLEAR_LBL),
+                    )
+                    .clicked()
+                {
+                    self.opening_state_file = None;
+                }
+                match &self.opening_state_file {
+                    Some(f) => ui.label(f.file_name().unwrap().display().to_string()),
+                    None => ui.label(INPUT_OPENING_STATE_HINT),
+                };
+            });
+            ui.horizontal(|ui| {
+                ui.label(INPUT_OUT_FOLDER_LBL);
+                if ui.button(BTN_BROWSE_LBL).clicked()
+                    && let Some(f) = FileDialog::new().pick_folder()
+                {
+                    self.out_folder = Some(f);
+                }
+                if ui
+                    .add_enabled(self.out_folder.is_some(), Button::new(BTN_CLEAR_LBL))
+                    .clicked()
+                {
+                    self.out_folder = None;
+                }
+                match &self.out_folder {
+                    Some(f) => ui.label(f.display().to_string()),
+                    None => ui.label(INPUT_OUT_FOLDER_HINT),
+                };
+            });
+            ui.horizontal(|ui| {
+                ui.label(INPUT_OUT_FILE_NAME_TEMPLATE_LBL);
+                ui.add(
+                    TextEdit::singleline(&mut self.out_file_name_template)
+                        .hint_text(DEFAULT_OUT_FILE_NAME_TEMPLATE)
+                        .desired_width(170.0),
+                )
+                .on_hover_text(INPUT_OUT_FILE_NAME_TEMPLATE_TOOLTIP);
+                ui.label(SELECTOR_OVERWRITE_POLICY_LBL);
+                ComboBox::from_id_salt(SELECTOR_OVERWRITE_POLICY_ID)
+                    .selected_text(self.overwrite_policy.to_string())
+                    .show_ui(ui, |ui| {
+                        for policy in OverwritePolicy::ALL {
+                            let text = policy.to_string();
+                            ui.selectable_value(&mut self.overwrite_policy, policy, text);
+                        }
+                    });
+            });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label(INPUT_HISTORY_APP_LBL);
+                ui.add(
+                    TextEdit::singleline(&mut self.history_app).hint_text(INPUT_HISTORY_APP_HINT),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label(INPUT_HISTORY_START_DATE_LBL);
+                ui.add(
+                    TextEdit::singleline(&mut self.history_start_date)
+                        .hint_text(INPUT_HISTORY_DATE_HINT)
+                        .desired_width(80.0),
+                );
+                ui.label(INPUT_HISTORY_END_DATE_LBL);
+                ui.add(
+                    TextEdit::singleline(&mut self.history_end_date)
+                        .hint_text(INPUT_HISTORY_DATE_HINT)
+                        .desired_width(80.0),
+                );
+                if ui
+                    .add_enabled(
+                        !self.history_app.trim().is_empty(),
+                        Button::new(BTN_ANALYZE_HISTORY_LBL),
+                    )
+                    .clicked()
+                {
+                    self.analyze(Some((&self.history_start_date, &self.history_end_date)));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label(INPUT_COMPARISON_START_DATE_LBL);
+                ui.add(
+                    TextEdit::singleline(&mut self.comparison_start_date)
+                        .hint_text(INPUT_HISTORY_DATE_HINT)
+                        .desired_width(80.0),
+                );
+                ui.label(INPUT_HISTORY_END_DATE_LBL);
+                ui.add(
+                    TextEdit::singleline(&mut self.comparison_end_date)
+                        .hint_text(INPUT_HISTORY_DATE_HINT)
+                        .desired_width(80.0),
+                );
+            });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button(BTN_COMPARE_FILE_PICKER_LBL).clicked() {
+                    let total_stats_file_list = FileDialog::new()
+                        .add_filter("json", &["json", "JSON"])
+                        .pick_files();
+                    if total_stats_file_list.is_some() {
+                        show_result(compare_from_gui(
+                            &total_stats_file_list,
+                            &self.out_folder,
+                            &self.out_file_name_template,
+                            &self.overwrite_policy,
+                        ));
+                    }
+                }
+                if ui.button(BTN_SUMMARY_FILE_PICKER_LBL).clicked()
+                    && let Some(total_stats_file_list) = FileDialog::new()
+                        .add_filter("json", &["json", "JSON"])
+                        .pick_files()
+                {
+                    show_result(summarize_from_files(&total_stats_file_list).map(|summary| {
+                        ui.ctx().copy_text(summary);
+                        format!(
+                            "{} {}",
+                            MARKDOWN_SUMMARY,
+                            message::success::COPIED_CLIPBOARD
+                        )
+                    }));
+                }
+            });
         });
     }
 }
@@ -190,9 +515,9 @@ pub fn run(reset_default: bool) -> eframe::Result<()> {
     let native_options = NativeOptions {
         viewport: ViewportBuilder::default()
             .with_position(pos2(400.0, 200.0))
-            .with_inner_size([400.0, 120.0])
-            .with_max_inner_size([400.0, 120.0])
-            .with_min_inner_size([400.0, 120.0])
+            .with_inner_size([400.0, 456.0])
+            .with_max_inner_size([400.0, 412.0])
+            .with_min_inner_size([400.0, 412.0])
             .with_resizable(false)
             .with_icon(
                 icon_data::from_png_bytes(&include_bytes!("../ass/icon/icon256.png")[..])
@@ -206,9 +531,9 @@ pub fn run(reset_default: bool) -> eframe::Result<()> {
     let native_options = NativeOptions {
         viewport: ViewportBuilder::default()
             .with_position(pos2(400.0, 200.0))
-            .with_inner_size([400.0, 120.0])
-            .with_max_inner_size([400.0, 120.0])
-            .with_min_inner_size([400.0, 120.0])
+            .with_inner_size([400.0, 456.0])
+            .with_max_inner_size([400.0, 412.0])
+            .with_min_inner_size([400.0, 412.0])
             .with_resizable(false)
             .with_icon(
                 icon_data::from_png_bytes(&include_bytes!("../ass/icon/icon1024mac.png")[..])
@@ -218,6 +543,9 @@ pub fn run(reset_default: bool) -> eframe::Result<()> {
         ..Default::default()
     };
 
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let native_options = NativeOptions::default();
+
     run_native(
         "Jisrot - Shopify Events Anal",
         native_options,
@@ -1,26 +1,62 @@
 use anyhow::anyhow;
+use chrono::NaiveDateTime;
 use std::path::PathBuf;
 
+use crate::definitions::common::*;
+use crate::definitions::strings::message;
 use crate::models::data_model::*;
-use indexmap::IndexMap;
+use crate::reports::html::render_html_report;
+use crate::reports::template::render_template_report;
+use indexmap::{IndexMap, IndexSet};
+use rusqlite::{Connection, params};
+use rust_xlsxwriter::{Format, Workbook, Worksheet};
+use serde::Serialize;
+use serde_json::Value;
 
 pub fn read_events_from_csv(
     source_file: &PathBuf,
     excluding_check_field: &str,
+    input_profile: &InputProfile,
 ) -> anyhow::Result<Vec<AppEvent>> {
     let mut rdr = csv::Reader::from_path(source_file)?;
-    let headers: Vec<String> = rdr.headers()?.iter().map(|h| h.to_string()).collect();
-
-    let mut app_event_list: Vec<AppEvent> = Vec::new();
+    let headers: Vec<String> = rdr
+        .headers()?
+        .iter()
+        .map(|h| input_profile.field_of_header(h))
+        .collect();
 
+    let mut record_list: Vec<IndexMap<String, String>> = Vec::new();
     for record in rdr.records() {
-        let record_hash: IndexMap<String, String> = record?
+        record_list.push(
+            record?
+                .iter()
+                .zip(headers.iter())
+                .map(|(value, header)| (header.to_string(), value.to_string()))
+                .collect(),
+        );
+    }
+
+    //  One format per column, so day and month first dates are not mixed in a file
+    let detect_column_format = |field: &str, formats: &[String]| {
+        let values: Vec<&str> = record_list
             .iter()
-            .zip(headers.iter())
-            .map(|(value, header)| (header.to_string(), value.to_string()))
+            .filter_map(|r| r.get(field).map(String::as_str))
             .collect();
+        InputProfile::detect_format(field, formats, &values)
+    };
+    let time_pattern: String = detect_column_format(TIME_FIELD, input_profile.time_formats())?;
+    let billing_on_pattern: String =
+        detect_column_format(BILLING_ON_FIELD, input_profile.billing_on_formats())?;
 
-        match AppEvent::from_indexmap(&record_hash, excluding_check_field) {
+    let mut app_event_list: Vec<AppEvent> = Vec::new();
+
+    for record_hash in record_list {
+        match AppEvent::from_indexmap(
+            &record_hash,
+            excluding_check_field,
+            &time_pattern,
+            &billing_on_pattern,
+        ) {
             Ok(app_event) => app_event_list.push(app_event),
             Err(e) => return Err(anyhow!("Error parsing record: {}", e)),
         }
@@ -29,6 +65,166 @@ pub fn read_events_from_csv(
     Ok(app_event_list)
 }
 
+/// App events of a saved Partner API `app { events }` GraphQL response.
+/// The file may hold one response or a list of responses of paginated queries
+pub fn read_events_from_partner_api_json(
+    source_file: &PathBuf,
+    excluding_check_field: &str,
+) -> anyhow::Result<Vec<AppEvent>> {
+    let file = std::fs::File::open(source_file)?;
+    let reader = std::io::BufReader::new(file);
+    let json: Value = serde_json::from_reader(reader)?;
+
+    let responses: Vec<&Value> = match &json {
+        Value::Array(pages) => pages.iter().collect(),
+        _ => vec![&json],
+    };
+
+    let mut app_event_list: Vec<AppEvent> = Vec::new();
+
+    for response in responses {
+        if let Some(errors) = response.get("errors").and_then(|e| e.as_array())
+            && !errors.is_empty()
+        {
+            return Err(anyhow!(
+                "Partner API response has errors: {}",
+                errors
+                    .iter()
+                    .map(|e| e["message"].as_str().unwrap_or_default())
+                    .collect::<Vec<&str>>()
+                    .join("; ")
+            ));
+        }
+
+        let events = &response["data"]["app"]["events"];
+        let nodes: Vec<&Value> = match (events["edges"].as_array(), events["nodes"].as_array()) {
+            (Some(edges), _) => edges.iter().map(|edge| &edge["node"]).collect(),
+            (None, Some(nodes)) => nodes.iter().collect(),
+            (None, None) => {
+                return Err(anyhow!(
+                    "No app events in Partner API response: {}",
+                    source_file.display()
+                ));
+            }
+        };
+
+        for node in nodes {
+            match AppEvent::from_indexmap(
+                &partner_api_event_record(node)?,
+                excluding_check_field,
+                EVENT_TIME_PATTERN,
+                BILLING_ON_PATTERN,
+            ) {
+                Ok(app_event) => app_event_list.push(app_event),
+                Err(e) => return Err(anyhow!("Error parsing record: {}", e)),
+            }
+        }
+    }
+
+    Ok(app_event_list)
+}
+
+/// Fields of a Partner API event node by the CSV columns of the same data
+fn partner_api_event_record(node: &Value) -> anyhow::Result<IndexMap<String, String>> {
+    let text = |value: &Value| match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    };
+
+    let event_type = node["type"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Partner API event without type: {}", node))?;
+    let event = PARTNER_API_EVENT_TYPES
+        .iter()
+        .find(|(api_type, _)| *api_type == event_type)
+        .map(|(_, csv_event)| csv_event.to_string())
+        .unwrap_or_else(|| event_type.to_string());
+
+    //  Time is given in RFC 3339, e.g. 2025-02-01T09:30:00.000000Z
+    let time = match node["occurredAt"].as_str() {
+        Some(occurred_at) => chrono::DateTime::parse_from_rfc3339(occurred_at)
+            .map(|t| t.naive_utc().format(EVENT_TIME_PATTERN).to_string())
+            .unwrap_or_else(|_| occurred_at.to_string()),
+        None => String::new(),
+    };
+
+    let shop = &node["shop"];
+    let charge = &node["charge"];
+
+    //  Charge name is the plan or pack in CSV, reason is given for uninstalls
+    let details = match charge["name"].as_str() {
+        Some(name) => name.to_string(),
+        None => [&node["reason"], &node["description"]]
+            .iter()
+            .map(|v| text(v))
+            .filter(|v| !v.is_empty())
+            .collect::<Vec<String>>()
+            .join(" - "),
+    };
+
+    let mut record: IndexMap<String, String> = IndexMap::new();
+    record.insert(TIME_FIELD.to_string(), time);
+    record.insert(EVENT_FIELD.to_string(), event);
+    record.insert(DETAILS_FIELD.to_string(), details);
+    record.insert(BILLING_ON_FIELD.to_string(), text(&charge["billingOn"]));
+    record.insert(SHOP_NAME_FIELD.to_string(), text(&shop["name"]));
+    record.insert(
+        SHOP_DOMAIN_FIELD.to_string(),
+        text(&shop["myshopifyDomain"]),
+    );
+    record.insert(PARTNER_API_SHOP_ID_FIELD.to_string(), text(&shop["id"]));
+
+    if charge.is_object() {
+        record.insert(PARTNER_API_CHARGE_ID_FIELD.to_string(), text(&charge["id"]));
+        record.insert(
+            PARTNER_API_AMOUNT_FIELD.to_string(),
+            text(&charge["amount"]["amount"]),
+        );
+        record.insert(
+            PARTNER_API_CURRENCY_FIELD.to_string(),
+            text(&charge["amount"]["currencyCode"]),
+        );
+        record.insert(PARTNER_API_TEST_FIELD.to_string(), text(&charge["test"]));
+    }
+
+    Ok(record)
+}
+
+/// Saved Partner API responses of a fetch, in fetching order
+pub fn read_partner_api_pages(source_file: &PathBuf) -> anyhow::Result<Vec<Value>> {
+    let file = std::fs::File::open(source_file)?;
+    let reader = std::io::BufReader::new(file);
+
+    let result = serde_json::from_reader(reader)?;
+    Ok(result)
+}
+
+pub fn read_fetch_checkpoint_from_json(source_file: &PathBuf) -> anyhow::Result<FetchCheckpoint> {
+    let file = std::fs::File::open(source_file)?;
+    let reader = std::io::BufReader::new(file);
+
+    let result = serde_json::from_reader(reader)?;
+    Ok(result)
+}
+
+/// App events of an event history file, a Partner API response by JSON extension or else CSV
+pub fn read_events_from_file(
+    source_file: &PathBuf,
+    excluding_check_field: &str,
+    input_profile: &InputProfile,
+) -> anyhow::Result<Vec<AppEvent>> {
+    let is_partner_api_json = source_file
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(PARTNER_API_FILE_EXTENSION));
+
+    if is_partner_api_json {
+        read_events_from_partner_api_json(source_file, excluding_check_field)
+    } else {
+        read_events_from_csv(source_file, excluding_check_field, input_profile)
+    }
+}
+
 pub fn read_pricing_def_from_json(source_file: &PathBuf) -> anyhow::Result<PricingDefs> {
     let file = std::fs::File::open(source_file)?;
     let reader = std::io::BufReader::new(file);
@@ -53,44 +249,1008 @@ pub fn read_excluding_def_from_json_str(json_str: &str) -> anyhow::Result<Exclud
     Ok(serde_json::from_str(json_str)?)
 }
 
-pub fn write_app_event_list_to_json(
-    file_out: &PathBuf,
-    app_event_list: &Vec<AppEvent>,
-) -> anyhow::Result<()> {
+pub fn read_input_profile_from_json(source_file: &PathBuf) -> anyhow::Result<InputProfile> {
+    let file = std::fs::File::open(source_file)?;
+    let reader = std::io::BufReader::new(file);
+
+    let result = serde_json::from_reader(reader)?;
+    Ok(result)
+}
+
+pub fn read_input_profile_from_json_str(json_str: &str) -> anyhow::Result<InputProfile> {
+    Ok(serde_json::from_str(json_str)?)
+}
+
+pub fn read_event_vocabulary_from_json(source_file: &PathBuf) -> anyhow::Result<EventVocabulary> {
+    let file = std::fs::File::open(source_file)?;
+    let reader = std::io::BufReader::new(file);
+
+    let result = serde_json::from_reader(reader)?;
+    Ok(result)
+}
+
+pub fn read_event_vocabulary_from_json_str(json_str: &str) -> anyhow::Result<EventVocabulary> {
+    Ok(serde_json::from_str(json_str)?)
+}
+
+pub fn read_total_stats_from_json(source_file: &PathBuf) -> anyhow::Result<TotalStats> {
+    let file = std::fs::File::open(source_file)?;
+    let reader = std::io::BufReader::new(file);
+
+    let result = serde_json::from_reader(reader)?;
+    Ok(result)
+}
+
+pub fn read_merchant_data_from_json(source_file: &PathBuf) -> anyhow::Result<MerchantList> {
+    let file = std::fs::File::open(source_file)?;
+    let reader = std::io::BufReader::new(file);
+
+    let result = serde_json::from_reader(reader)?;
+    Ok(result)
+}
+
+/// Pretty JSON of any output data, e.g. total stats, merchant data or app events
+pub fn write_to_json<T: Serialize + ?Sized>(file_out: &PathBuf, data: &T) -> anyhow::Result<()> {
     if let Some(parent) = file_out.parent() {
         std::fs::create_dir_all(parent)?;
     }
     let file = std::fs::File::create(file_out)?;
     let writer = std::io::BufWriter::new(file);
 
-    serde_json::to_writer_pretty(writer, app_event_list)?;
+    serde_json::to_writer_pretty(writer, data)?;
     Ok(())
 }
 
-pub fn write_total_stats_to_json(
+/// Long format, one row per flattened total stats field in a stable order
+pub fn write_total_stats_to_csv(
     file_out: &PathBuf,
     total_stats: &TotalStats,
 ) -> anyhow::Result<()> {
     if let Some(parent) = file_out.parent() {
         std::fs::create_dir_all(parent)?;
     }
-    let file = std::fs::File::create(file_out)?;
-    let writer = std::io::BufWriter::new(file);
+    let mut wtr = csv::Writer::from_path(file_out)?;
 
-    serde_json::to_writer_pretty(writer, total_stats)?;
+    wtr.write_record(["metric", "value"])?;
+    for (metric, value) in flatten_to_pairs(total_stats)? {
+        wtr.write_record([metric, value])?;
+    }
+
+    wtr.flush()?;
     Ok(())
 }
 
-pub fn write_merchant_data_to_json(
+fn plan_code(plan: &Option<PricingUnit>) -> String {
+    plan.as_ref().map(|p| p.code().clone()).unwrap_or_default()
+}
+
+fn hours(hours: &Option<f64>) -> String {
+    hours.map(|h| h.to_string()).unwrap_or_default()
+}
+
+fn billing_cycle(billing_cycle: &Option<BillingCycle>) -> String {
+    billing_cycle
+        .as_ref()
+        .map(|c| format!("{:?}", c))
+        .unwrap_or_default()
+}
+
+/// Flat merchant record for CSV and XLSX, one-time pack counts come last in pricing definitions order
+fn merchant_record(merchant: &Merchant) -> Vec<(String, String)> {
+    let mut record: Vec<(String, String)> = vec![
+        ("shop_domain", merchant.shop_domain().clone()),
+        ("installed_status", merchant.installed_status().clone()),
+        (
+            "subscription_status",
+            merchant.subscription_status().clone(),
+        ),
+        ("last_new_sub_plan", plan_code(merchant.last_new_sub_plan())),
+        (
+            "last_new_sub_billing_cycle",
+            billing_cycle(merchant.last_new_sub_billing_cycle()),
+        ),
+        (
+            "first_canceled_sub_plan",
+            plan_code(merchant.first_canceled_sub_plan()),
+        ),
+        (
+            "first_canceled_sub_billing_cycle",
+            billing_cycle(merchant.first_canceled_sub_billing_cycle()),
+        ),
+        ("active_at_start", merchant.active_at_start().to_string()),
+        ("sub_plan_at_start", plan_code(merchant.sub_plan_at_start())),
+        (
+            "sub_billing_cycle_at_start",
+            billing_cycle(merchant.sub_billing_cycle_at_start()),
+        ),
+        ("active_at_end", merchant.active_at_end().to_string()),
+        ("sub_plan_at_end", plan_code(merchant.sub_plan_at_end())),
+        (
+            "sub_billing_cycle_at_end",
+            billing_cycle(merchant.sub_billing_cycle_at_end()),
+        ),
+        ("trial_status", merchant.trial_status().clone()),
+        ("trial_plan", plan_code(merchant.trial_plan())),
+        ("installed_count", merchant.installed_count().to_string()),
+        (
+            "uninstalled_count",
+            merchant.uninstalled_count().to_string(),
+        ),
+        (
+            "store_closed_count",
+            merchant.store_closed_count().to_string(),
+        ),
+        (
+            "store_reopened_count",
+            merchant.store_reopened_count().to_string(),
+        ),
+        ("store_status", merchant.store_status().clone()),
+        (
+            "store_closed_at_start",
+            merchant.store_closed_at_start().to_string(),
+        ),
+        (
+            "store_closed_at_end",
+            merchant.store_closed_at_end().to_string(),
+        ),
+        ("days_closed", merchant.days_closed().to_string()),
+        (
+            "subscription_activated_count",
+            merchant.subscription_activated_count().to_string(),
+        ),
+        (
+            "subscription_canceled_count",
+            merchant.subscription_canceled_count().to_string(),
+        ),
+        (
+            "subscription_frozen_count",
+            merchant.subscription_frozen_count().to_string(),
+        ),
+        (
+            "subscription_unfrozen_count",
+            merchant.subscription_unfrozen_count().to_string(),
+        ),
+        (
+            "reactivation_count",
+            merchant.reactivations().len().to_string(),
+        ),
+        (
+            "hours_to_first_subscription",
+            hours(merchant.hours_to_first_subscription()),
+        ),
+        (
+            "hours_to_first_cancel",
+            hours(merchant.hours_to_first_cancel()),
+        ),
+        ("hours_to_uninstall", hours(merchant.hours_to_uninstall())),
+        (
+            "subscription_revenue",
+            merchant.subscription_revenue().to_string(),
+        ),
+        ("one_time_revenue", merchant.one_time_revenue().to_string()),
+        ("lifetime_revenue", merchant.lifetime_revenue().to_string()),
+        ("tenure_days", merchant.tenure_days().to_string()),
+        ("one_time_count", merchant.one_time_count().to_string()),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v))
+    .collect();
+
+    for (pack, count) in merchant.one_time_details() {
+        record.push((format!("one_time.{}", pack), count.to_string()));
+    }
+    record
+}
+
+/// One row per merchant
+pub fn write_merchant_data_to_csv(
     file_out: &PathBuf,
     merchant_data_list: &MerchantList,
 ) -> anyhow::Result<()> {
     if let Some(parent) = file_out.parent() {
         std::fs::create_dir_all(parent)?;
     }
-    let file = std::fs::File::create(file_out)?;
-    let writer = std::io::BufWriter::new(file);
+    let mut wtr = csv::Writer::from_path(file_out)?;
+
+    let records: Vec<Vec<(String, String)>> = merchant_data_list
+        .merchants()
+        .values()
+        .map(merchant_record)
+        .collect();
+
+    if let Some(first_record) = records.first() {
+        wtr.write_record(first_record.iter().map(|(k, _)| k))?;
+    }
+    for record in records {
+        wtr.write_record(record.into_iter().map(|(_, v)| v))?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// One row per metric, per-plan changes are named as `sub_stats_details.<counter>.<plan>.<billing cycle>`
+pub fn write_stats_comparison_to_csv(
+    file_out: &PathBuf,
+    stats_comparison: &StatsComparison,
+) -> anyhow::Result<()> {
+    if let Some(parent) = file_out.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut wtr = csv::Writer::from_path(file_out)?;
+
+    wtr.write_record(["metric", "base", "current", "change", "percent_change"])?;
+
+    let mut write_row = |metric: String, metric_change: &MetricChange| {
+        wtr.write_record([
+            metric,
+            metric_change.base().to_string(),
+            metric_change.current().to_string(),
+            metric_change.change().to_string(),
+            metric_change
+                .percent_change()
+                .map(|p| p.to_string())
+                .unwrap_or_default(),
+        ])
+    };
+
+    for (metric, metric_change) in stats_comparison.metrics() {
+        write_row(metric.clone(), metric_change)?;
+    }
+    for (counter, plan_changes) in stats_comparison.sub_stats_details() {
+        for (plan, plan_change) in plan_changes {
+            let metric = format!("sub_stats_details.{}.{}", counter, plan);
+            write_row(format!("{}.monthly", metric), plan_change.monthly())?;
+            write_row(format!("{}.yearly", metric), plan_change.yearly())?;
+        }
+    }
 
-    serde_json::to_writer_pretty(writer, merchant_data_list)?;
+    wtr.flush()?;
     Ok(())
 }
+
+/// Flatten serialized data into (path, value) pairs, nested keys are joined with "."
+pub fn flatten_to_pairs<T: Serialize>(data: &T) -> anyhow::Result<Vec<(String, String)>> {
+    fn flatten(prefix: &str, value: &Value, pairs: &mut Vec<(String, String)>) {
+        match value {
+            Value::Object(map) => {
+                for (key, child) in map {
+                    let path = if prefix.is_empty() {
+                        key.to_string()
+                    } else {
+                        format!("{}.{}", prefix, key)
+                    };
+                    flatten(&path, child, pairs);
+                }
+            }
+            Value::Array(list) => {
+                for (index, child) in list.iter().enumerate() {
+                    flatten(&format!("{}.{}", prefix, index), child, pairs);
+                }
+            }
+            Value::String(s) => pairs.push((prefix.to_string(), s.clone())),
+            Value::Null => pairs.push((prefix.to_string(), String::new())),
+            other => pairs.push((prefix.to_string(), other.to_string())),
+        }
+    }
+
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    flatten("", &serde_json::to_value(data)?, &mut pairs);
+    Ok(pairs)
+}
+
+/// One row per group at every level, one column per dimension then per flattened total stats field.
+/// Dimension columns below the group level are left empty, so are fields missing in a group (e.g. cohorts).
+pub fn write_breakdown_stats_to_csv(
+    file_out: &PathBuf,
+    breakdown_stats: &BreakdownStats,
+) -> anyhow::Result<()> {
+    fn collect_rows(
+        breakdown_stats: &BreakdownStats,
+        parent_groups: &[String],
+        rows: &mut Vec<(Vec<String>, IndexMap<String, String>)>,
+    ) -> anyhow::Result<()> {
+        for (group, group_data) in breakdown_stats.groups() {
+            let mut group_path = parent_groups.to_vec();
+            group_path.push(group.clone());
+
+            rows.push((
+                group_path.clone(),
+                flatten_to_pairs(group_data.stats())?.into_iter().collect(),
+            ));
+
+            if let Some(next) = group_data.breakdown() {
+                collect_rows(next, &group_path, rows)?;
+            }
+        }
+        Ok(())
+    }
+
+    let mut rows: Vec<(Vec<String>, IndexMap<String, String>)> = Vec::new();
+    collect_rows(breakdown_stats, &[], &mut rows)?;
+
+    let dimensions = breakdown_stats.dimensions();
+    let mut fields: IndexSet<String> = IndexSet::new();
+    for (_, values) in rows.iter() {
+        fields.extend(values.keys().cloned());
+    }
+
+    if let Some(parent) = file_out.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut wtr = csv::Writer::from_path(file_out)?;
+
+    wtr.write_record(dimensions.iter().chain(fields.iter()))?;
+
+    for (group_path, values) in rows {
+        let mut record = group_path;
+        record.resize(dimensions.len(), String::new());
+        record.extend(
+            fields
+                .iter()
+                .map(|f| values.get(f).cloned().unwrap_or_default()),
+        );
+        wtr.write_record(&record)?;
+    }
+
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Open the history database, creating it if needed.
+/// Events are kept per app, an event already imported is identified by its app, time, shop, event and details.
+pub fn open_history_db(db_file: &PathBuf) -> anyhow::Result<Connection> {
+    if let Some(parent) = db_file.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let conn = Connection::open(db_file)?;
+
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS app_events (
+            id INTEGER PRIMARY KEY,
+            app TEXT NOT NULL,
+            time TEXT NOT NULL,
+            event TEXT NOT NULL,
+            details TEXT NOT NULL,
+            billing_on TEXT,
+            shop_name TEXT NOT NULL,
+            shop_country TEXT NOT NULL,
+            shop_email TEXT NOT NULL,
+            shop_domain TEXT NOT NULL,
+            other_fields TEXT NOT NULL,
+            UNIQUE (app, time, shop_domain, event, details)
+        );
+        CREATE INDEX IF NOT EXISTS app_events_app_time ON app_events (app, time);",
+    )?;
+    Ok(conn)
+}
+
+/// Import events of an app into the history database, return the number of events not imported before
+pub fn write_events_to_history_db(
+    conn: &Connection,
+    app: &str,
+    app_event_list: &Vec<AppEvent>,
+) -> anyhow::Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let mut imported_count: usize = 0;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT OR IGNORE INTO app_events
+                (app, time, event, details, billing_on, shop_name, shop_country, shop_email, shop_domain, other_fields)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        )?;
+
+        for event in app_event_list {
+            //  Time is part of the unique key, events without it cannot be told apart
+            let Some(time) = event.time() else {
+                println!(
+                    "Warning: {}: {} {} {}",
+                    message::warning::EVENT_WITHOUT_TIME_NOT_KEPT,
+                    event.shop_domain(),
+                    event.event(),
+                    event.details()
+                );
+                continue;
+            };
+            imported_count += stmt.execute(params![
+                app,
+                time.format(HISTORY_TIME_PATTERN).to_string(),
+                event.event(),
+                event.details(),
+                event
+                    .billing_on()
+                    .map(|t| t.format(HISTORY_TIME_PATTERN).to_string()),
+                event.shop_name(),
+                event.shop_country(),
+                event.shop_email(),
+                event.shop_domain(),
+                serde_json::to_string(event.other_fields())?,
+            ])?;
+        }
+    }
+    tx.commit()?;
+
+    Ok(imported_count)
+}
+
+/// Read events of an app from the history database in time order, optionally limited to a period.
+/// Excluding check data is taken from the given field, as it is when reading from CSV.
+pub fn read_events_from_history_db(
+    conn: &Connection,
+    app: &str,
+    start_time: Option<NaiveDateTime>,
+    end_time: Option<NaiveDateTime>,
+    excluding_check_field: &str,
+) -> anyhow::Result<Vec<AppEvent>> {
+    let mut stmt = conn.prepare(
+        "SELECT time, event, details, billing_on, shop_name, shop_country, shop_email, shop_domain, other_fields
+        FROM app_events
+        WHERE app = ?1 AND (?2 IS NULL OR time >= ?2) AND (?3 IS NULL OR time <= ?3)
+        ORDER BY time, id",
+    )?;
+
+    let parse_time = |time_str: Option<String>| {
+        time_str.and_then(|t| NaiveDateTime::parse_from_str(&t, HISTORY_TIME_PATTERN).ok())
+    };
+
+    let mut rows = stmt.query(params![
+        app,
+        start_time.map(|t| t.format(HISTORY_TIME_PATTERN).to_string()),
+        end_time.map(|t| t.format(HISTORY_TIME_PATTERN).to_string()),
+    ])?;
+
+    let mut app_event_list: Vec<AppEvent> = Vec::new();
+    while let Some(row) = rows.next()? {
+        let mut app_event = AppEvent::new();
+        app_event.set_time(parse_time(row.get(0)?));
+        app_event.set_event(row.get(1)?);
+        app_event.set_details(row.get(2)?);
+        app_event.set_billing_on(parse_time(row.get(3)?));
+        app_event.set_shop_name(row.get(4)?);
+        app_event.set_shop_country(row.get(5)?);
+        app_event.set_shop_email(row.get(6)?);
+        app_event.set_shop_domain(row.get(7)?);
+        app_event.set_other_fields(serde_json::from_str(&row.get::<_, String>(8)?)?);
+        app_event.set_excluding_check_data(app_event.field_value(excluding_check_field));
+
+        app_event_list.push(app_event);
+    }
+
+    Ok(app_event_list)
+}
+
+/// Self-contained HTML report, viewable offline
+pub fn write_report_to_html(
+    file_out: &PathBuf,
+    total_stats: &TotalStats,
+    merchant_data_list: &MerchantList,
+) -> anyhow::Result<()> {
+    if let Some(parent) = file_out.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(
+        file_out,
+        render_html_report(total_stats, merchant_data_list),
+    )?;
+
+    Ok(())
+}
+
+pub fn read_report_template(source_file: &PathBuf) -> anyhow::Result<String> {
+    Ok(std::fs::read_to_string(source_file)?)
+}
+
+/// Report rendered from a template with total stats and merchant data
+pub fn write_template_report(
+    file_out: &PathBuf,
+    template_name: &str,
+    template_source: &str,
+    total_stats: &TotalStats,
+    merchant_data_list: &MerchantList,
+) -> anyhow::Result<()> {
+    let rendered = render_template_report(
+        template_name,
+        template_source,
+        total_stats,
+        merchant_data_list,
+    )?;
+
+    if let Some(parent) = file_out.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(file_out, rendered)?;
+
+    Ok(())
+}
+
+/// Workbook report with Summary, Subscriptions, One-time packs and Merchants sheets,
+/// and an App Events sheet if the event list is given
+pub fn write_report_to_xlsx(
+    file_out: &PathBuf,
+    total_stats: &TotalStats,
+    merchant_data_list: &MerchantList,
+    app_event_list: Option<&Vec<AppEvent>>,
+) -> anyhow::Result<()> {
+    let header_format = Format::new().set_bold();
+    let integer_format = Format::new().set_num_format(XLSX_INTEGER_FORMAT);
+    let decimal_format = Format::new().set_num_format(XLSX_DECIMAL_FORMAT);
+    let money_format = Format::new().set_num_format(XLSX_MONEY_FORMAT);
+    let percent_format = Format::new().set_num_format(XLSX_PERCENT_FORMAT);
+    let datetime_format = Format::new().set_num_format(XLSX_DATETIME_FORMAT);
+
+    //  Number cells get a format from the field name, rates are stored as percent numbers (e.g. 12.5)
+    let write_value = |worksheet: &mut Worksheet,
+                       row: u32,
+                       col: u16,
+                       field: &str,
+                       value: &str|
+     -> anyhow::Result<()> {
+        let field_name = field.rsplit('.').next().unwrap_or(field);
+
+        match value.parse::<f64>() {
+            Ok(number) if XLSX_PERCENT_FIELDS.contains(&field_name) => {
+                worksheet.write_number_with_format(row, col, number / 100.0, &percent_format)?;
+            }
+            Ok(number)
+                if XLSX_MONEY_FIELD_KEYWORDS
+                    .iter()
+                    .any(|keyword| field_name.contains(keyword)) =>
+            {
+                worksheet.write_number_with_format(row, col, number, &money_format)?;
+            }
+            Ok(number) if number.fract() == 0.0 => {
+                worksheet.write_number_with_format(row, col, number, &integer_format)?;
+            }
+            Ok(number) => {
+                worksheet.write_number_with_format(row, col, number, &decimal_format)?;
+            }
+            Err(_) => {
+                worksheet.write_string(row, col, value)?;
+            }
+        }
+        Ok(())
+    };
+
+    let write_header = |worksheet: &mut Worksheet, headers: &Vec<String>| -> anyhow::Result<()> {
+        for (col, header) in headers.iter().enumerate() {
+            worksheet.write_string_with_format(0, col as u16, header, &header_format)?;
+        }
+        worksheet.set_freeze_panes(1, 0)?;
+        Ok(())
+    };
+
+    let mut workbook = Workbook::new();
+
+    //  Summary: period, then every field not detailed in other sheets
+    let worksheet = workbook.add_worksheet().set_name(XLSX_SHEET_SUMMARY)?;
+    write_header(worksheet, &vec!["metric".to_string(), "value".to_string()])?;
+
+    let mut row: u32 = 1;
+    for (field, time) in [
+        ("start_time", total_stats.start_time()),
+        ("end_time", total_stats.end_time()),
+    ] {
+        worksheet.write_string(row, 0, field)?;
+        if let Some(t) = time {
+            worksheet.write_datetime_with_format(row, 1, t, &datetime_format)?;
+        }
+        row += 1;
+    }
+
+    for (field, value) in flatten_to_pairs(total_stats)? {
+        if XLSX_SUMMARY_SKIPPED_FIELDS
+            .iter()
+            .any(|skipped| field == *skipped || field.starts_with(&format!("{}.", skipped)))
+        {
+            continue;
+        }
+        worksheet.write_string(row, 0, &field)?;
+        write_value(worksheet, row, 1, &field, &value)?;
+        row += 1;
+    }
+    worksheet.autofit();
+
+    //  Subscriptions: one row per plan and billing cycle
+    let worksheet = workbook
+        .add_worksheet()
+        .set_name(XLSX_SHEET_SUBSCRIPTIONS)?;
+    let details = total_stats.sub_stats_details();
+    let counters = [
+        ("new_sub", details.new_sub()),
+        ("canceled_sub", details.canceled_sub()),
+        ("sub_growth", details.sub_growth()),
+        ("all_new_sub", details.all_new_sub()),
+        ("all_canceled_sub", details.all_canceled_sub()),
+    ];
+
+    let mut headers: Vec<String> = vec![
+        "plan".to_string(),
+        "billing_cycle".to_string(),
+        "paying".to_string(),
+    ];
+    headers.extend(counters.iter().map(|(counter, _)| counter.to_string()));
+    write_header(worksheet, &headers)?;
+
+    row = 1;
+    for plan in details.new_sub().monthly_counts().keys() {
+        let paying = total_stats
+            .paying_sub_stats_details()
+            .new_sub()
+            .monthly_counts()
+            .contains_key(plan);
+
+        for billing_cycle in [BillingCycle::Monthly, BillingCycle::Yearly] {
+            worksheet.write_string(row, 0, plan)?;
+            worksheet.write_string(row, 1, format!("{:?}", billing_cycle))?;
+            worksheet.write_string(row, 2, if paying { YES } else { NO })?;
+
+            for (col, (_, counter)) in counters.iter().enumerate() {
+                let counts = match billing_cycle {
+                    BillingCycle::Monthly => counter.monthly_counts(),
+                    BillingCycle::Yearly => counter.yearly_counts(),
+                };
+                worksheet.write_number_with_format(
+                    row,
+                    (col + 3) as u16,
+                    *counts.get(plan).unwrap_or(&0) as f64,
+                    &integer_format,
+                )?;
+            }
+            row += 1;
+        }
+    }
+    worksheet.autofit();
+
+    //  One-time packs: all packs first, then each pack
+    let worksheet = workbook.add_worksheet().set_name(XLSX_SHEET_ONE_TIMES)?;
+    let mut pack_rows: Vec<(String, Vec<(String, String)>)> = vec![(
+        XLSX_ALL_PACKS.to_string(),
+        flatten_to_pairs(total_stats.one_time_summary())?,
+    )];
+    for (pack, pack_stats) in total_stats.one_time_pack_stats() {
+        pack_rows.push((pack.clone(), flatten_to_pairs(pack_stats)?));
+    }
+
+    let mut headers: Vec<String> = vec!["pack".to_string()];
+    headers.extend(pack_rows[0].1.iter().map(|(field, _)| field.clone()));
+    write_header(worksheet, &headers)?;
+
+    for (index, (pack, pairs)) in pack_rows.iter().enumerate() {
+        row = index as u32 + 1;
+        worksheet.write_string(row, 0, pack)?;
+        for (col, (field, value)) in pairs.iter().enumerate() {
+            write_value(worksheet, row, (col + 1) as u16, field, value)?;
+        }
+    }
+    worksheet.autofit();
+
+    //  Merchants
+    let worksheet = workbook.add_worksheet().set_name(XLSX_SHEET_MERCHANTS)?;
+    let records: Vec<Vec<(String, String)>> = merchant_data_list
+        .merchants()
+        .values()
+        .map(merchant_record)
+        .collect();
+
+    if let Some(first_record) = records.first() {
+        write_header(
+            worksheet,
+            &first_record.iter().map(|(k, _)| k.clone()).collect(),
+        )?;
+    }
+    for (index, record) in records.iter().enumerate() {
+        row = index as u32 + 1;
+        for (col, (field, value)) in record.iter().enumerate() {
+            //  Shop domains and plan codes stay text even if they look like numbers
+            if col == 0 {
+                worksheet.write_string(row, 0, value)?;
+            } else {
+                write_value(worksheet, row, col as u16, field, value)?;
+            }
+        }
+    }
+    worksheet.autofit();
+
+    //  Raw app events
+    if let Some(app_event_list) = app_event_list {
+        let worksheet = workbook.add_worksheet().set_name(XLSX_SHEET_APP_EVENTS)?;
+
+        let mut other_fields: IndexSet<&String> = IndexSet::new();
+        for event in app_event_list {
+            other_fields.extend(event.other_fields().keys());
+        }
+
+        let mut headers: Vec<String> = KNOWN_FIELDS.iter().map(|f| f.to_string()).collect();
+        headers.extend(other_fields.iter().map(|f| f.to_string()));
+        write_header(worksheet, &headers)?;
+
+        for (index, event) in app_event_list.iter().enumerate() {
+            row = index as u32 + 1;
+            for (col, field) in headers.iter().enumerate() {
+                let time = match field.as_str() {
+                    TIME_FIELD => event.time(),
+                    BILLING_ON_FIELD => event.billing_on(),
+                    _ => {
+                        worksheet.write_string(row, col as u16, event.field_value(field))?;
+                        continue;
+                    }
+                };
+                if let Some(t) = time {
+                    worksheet.write_datetime_with_format(row, col as u16, t, &datetime_format)?;
+                }
+            }
+        }
+        worksheet.autofit();
+    }
+
+    if let Some(parent) = file_out.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    workbook.save(file_out)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definitions::default_event_vocabulary::SHOPIFY_EVENT_VOCABULARY_JSON_STRING;
+    use crate::definitions::default_ms_pricing_def::SBM_PRICING_DEF_JSON_STRING;
+
+    #[test]
+    fn breakdown_csv_header_covers_fields_of_every_group() {
+        let pricing_defs = read_pricing_def_from_json_str(SBM_PRICING_DEF_JSON_STRING).unwrap();
+
+        //  Groups with different install cohorts flatten to different fields
+        let mut us_stats = TotalStats::new(&pricing_defs);
+        us_stats
+            .revenue_stats_mut()
+            .cohorts_mut()
+            .insert("2025-01".to_string(), CohortStats::new());
+        let mut vn_stats = TotalStats::new(&pricing_defs);
+        vn_stats
+            .revenue_stats_mut()
+            .cohorts_mut()
+            .insert("2025-02".to_string(), CohortStats::new());
+
+        let mut breakdown_stats = BreakdownStats::new("country".to_string());
+        breakdown_stats.insert_group("US".to_string(), us_stats, None);
+        breakdown_stats.insert_group("VN".to_string(), vn_stats, None);
+
+        let file_out = std::env::temp_dir().join(format!(
+            "jisrot_breakdown_header_{}.csv",
+            std::process::id()
+        ));
+        write_breakdown_stats_to_csv(&file_out, &breakdown_stats).unwrap();
+
+        let mut reader = csv::Reader::from_path(&file_out).unwrap();
+        let header = reader.headers().unwrap().clone();
+        let records: Vec<csv::StringRecord> = reader.records().map(|r| r.unwrap()).collect();
+        std::fs::remove_file(&file_out).unwrap();
+
+        assert!(
+            header
+                .iter()
+                .any(|h| h.starts_with("revenue_stats.cohorts.2025-01."))
+        );
+        assert!(
+            header
+                .iter()
+                .any(|h| h.starts_with("revenue_stats.cohorts.2025-02."))
+        );
+        assert_eq!(records.len(), 2);
+        for record in records.iter() {
+            assert_eq!(record.len(), header.len());
+        }
+    }
+
+    #[test]
+    fn history_db_does_not_keep_events_without_time() {
+        let event = |time: Option<NaiveDateTime>, details: &str| {
+            let mut event = AppEvent::new();
+            event.set_time(time);
+            event.set_event("Subscription charge activated".to_string());
+            event.set_details(details.to_string());
+            event.set_shop_domain("shop.myshopify.com".to_string());
+            event
+        };
+        let time = NaiveDateTime::parse_from_str("2025-01-01 00:00:00", HISTORY_TIME_PATTERN).ok();
+        let events = vec![
+            event(time, "Pro plan"),
+            event(None, "Basic plan"),
+            event(None, "Pro plan"),
+        ];
+
+        let db_file =
+            std::env::temp_dir().join(format!("jisrot_history_time_{}.sqlite", std::process::id()));
+        let conn = open_history_db(&db_file).unwrap();
+        let imported_count = write_events_to_history_db(&conn, "app", &events).unwrap();
+        let kept_events = read_events_from_history_db(&conn, "app", None, None, "").unwrap();
+        drop(conn);
+        std::fs::remove_file(&db_file).unwrap();
+
+        assert_eq!(imported_count, 1);
+        assert_eq!(kept_events.len(), 1);
+        assert_eq!(kept_events[0].details(), "Pro plan");
+    }
+
+    #[test]
+    fn total_stats_written_before_newer_fields_still_loads() {
+        let pricing_defs = read_pricing_def_from_json_str(SBM_PRICING_DEF_JSON_STRING).unwrap();
+        let first_fields = [
+            "start_time",
+            "end_time",
+            "start_time_str",
+            "end_time_str",
+            "installed_count",
+            "uninstalled_count",
+            "old_uninstalled_count",
+            "total_churn_rate",
+            "churn_rate",
+            "merchant_growth",
+            "store_closed_count",
+            "store_reopened_count",
+            "one_time_count",
+            "one_time_details",
+            "new_sub_count",
+            "canceled_sub_count",
+            "sub_growth",
+            "sub_stats_details",
+            "paid_growth",
+        ];
+        let mut total_stats = serde_json::to_value(TotalStats::new(&pricing_defs)).unwrap();
+        total_stats
+            .as_object_mut()
+            .unwrap()
+            .retain(|field, _| first_fields.contains(&field.as_str()));
+
+        let file_in = std::env::temp_dir().join(format!(
+            "jisrot_old_total_stats_{}.json",
+            std::process::id()
+        ));
+        std::fs::write(&file_in, total_stats.to_string()).unwrap();
+        let loaded = read_total_stats_from_json(&file_in);
+        std::fs::remove_file(&file_in).unwrap();
+
+        let loaded = loaded.unwrap();
+        assert_eq!(*loaded.new_paying_sub_count(), 0);
+        assert_eq!(*loaded.headline_churn_formula(), ChurnFormula::default());
+    }
+
+    #[test]
+    fn xlsx_report_keeps_numbers_as_numbers_and_events_optional() {
+        use calamine::{Data, Reader, Xlsx, open_workbook};
+
+        let pricing_defs = read_pricing_def_from_json_str(SBM_PRICING_DEF_JSON_STRING).unwrap();
+        let mut total_stats = TotalStats::new(&pricing_defs);
+        total_stats.set_total_churn_rate(12.5);
+        let mut merchant_data = MerchantList::new();
+        merchant_data.update_merchant(Merchant::new(&"123".to_string(), pricing_defs.one_times()));
+        let mut event = AppEvent::new();
+        event.set_shop_domain("123".to_string());
+
+        let file_out =
+            std::env::temp_dir().join(format!("jisrot_report_{}.xlsx", std::process::id()));
+
+        write_report_to_xlsx(&file_out, &total_stats, &merchant_data, Some(&vec![event])).unwrap();
+        let mut workbook: Xlsx<_> = open_workbook(&file_out).unwrap();
+        assert_eq!(
+            workbook.sheet_names(),
+            [
+                XLSX_SHEET_SUMMARY,
+                XLSX_SHEET_SUBSCRIPTIONS,
+                XLSX_SHEET_ONE_TIMES,
+                XLSX_SHEET_MERCHANTS,
+                XLSX_SHEET_APP_EVENTS,
+            ]
+        );
+
+        //  Percent fields are stored as fractions to show with the percent format
+        let summary = workbook.worksheet_range(XLSX_SHEET_SUMMARY).unwrap();
+        let churn_row = summary
+            .rows()
+            .find(|r| r[0] == Data::String("total_churn_rate".to_string()))
+            .unwrap();
+        assert_eq!(churn_row[1], Data::Float(0.125));
+
+        //  A shop domain looking like a number stays text
+        let merchants = workbook.worksheet_range(XLSX_SHEET_MERCHANTS).unwrap();
+        assert_eq!(
+            merchants.get((0, 0)),
+            Some(&Data::String("shop_domain".to_string()))
+        );
+        assert_eq!(
+            merchants.get((1, 0)),
+            Some(&Data::String("123".to_string()))
+        );
+
+        write_report_to_xlsx(&file_out, &total_stats, &merchant_data, None).unwrap();
+        let workbook: Xlsx<_> = open_workbook(&file_out).unwrap();
+        assert!(
+            !workbook
+                .sheet_names()
+                .contains(&XLSX_SHEET_APP_EVENTS.to_string())
+        );
+        std::fs::remove_file(&file_out).unwrap();
+    }
+
+    #[test]
+    fn partner_api_events_read_and_classify_like_csv_events() {
+        //  A fetch saves its pages as a list, edges and nodes are both accepted
+        let pages = r#"[
+            {"data": {"app": {"events": {"edges": [
+                {"node": {
+                    "type": "RELATIONSHIP_INSTALLED",
+                    "occurredAt": "2025-02-01T09:30:00.000000Z",
+                    "shop": {"id": "gid://shopify/Shop/1", "name": "Shop", "myshopifyDomain": "shop.myshopify.com"}
+                }},
+                {"node": {
+                    "type": "SUBSCRIPTION_CHARGE_ACTIVATED",
+                    "occurredAt": "2025-02-01T10:00:00Z",
+                    "shop": {"id": "gid://shopify/Shop/1", "name": "Shop", "myshopifyDomain": "shop.myshopify.com"},
+                    "charge": {"id": "gid://shopify/AppSubscription/2", "name": "Pro plan", "test": false,
+                        "billingOn": "2025-02-08", "amount": {"amount": "9.99", "currencyCode": "USD"}}
+                }}
+            ]}}}},
+            {"data": {"app": {"events": {"nodes": [
+                {
+                    "type": "RELATIONSHIP_UNINSTALLED",
+                    "occurredAt": "2025-03-01T00:00:00Z",
+                    "reason": "Too expensive",
+                    "description": "Moved to another app",
+                    "shop": {"id": "gid://shopify/Shop/1", "name": "Shop", "myshopifyDomain": "shop.myshopify.com"}
+                }
+            ]}}}}
+        ]"#;
+
+        let file_in = std::env::temp_dir().join(format!(
+            "jisrot_partner_api_events_{}.json",
+            std::process::id()
+        ));
+        std::fs::write(&file_in, pages).unwrap();
+        let events = read_events_from_file(&file_in, SHOP_DOMAIN_FIELD, &InputProfile::default());
+        std::fs::remove_file(&file_in).unwrap();
+
+        let mut events = events.unwrap();
+        let vocabulary =
+            read_event_vocabulary_from_json_str(SHOPIFY_EVENT_VOCABULARY_JSON_STRING).unwrap();
+        vocabulary.classify_events(&mut events, false).unwrap();
+
+        let categories: Vec<Option<EventCategory>> = events.iter().map(|e| *e.category()).collect();
+        assert_eq!(
+            categories,
+            vec![
+                Some(EventCategory::Installed),
+                Some(EventCategory::SubscriptionActivated),
+                Some(EventCategory::Uninstalled),
+            ]
+        );
+
+        assert_eq!(events[0].field_value(TIME_FIELD), "2025-02-01 09:30:00");
+        assert_eq!(events[0].shop_domain(), "shop.myshopify.com");
+        assert_eq!(events[1].details(), "Pro plan");
+        assert_eq!(
+            events[1].field_value(BILLING_ON_FIELD),
+            "2025-02-08 00:00:00"
+        );
+        assert_eq!(events[1].field_value(PARTNER_API_AMOUNT_FIELD), "9.99");
+        assert_eq!(events[2].details(), "Too expensive - Moved to another app");
+    }
+
+    #[test]
+    fn partner_api_response_errors_are_reported() {
+        let file_in = std::env::temp_dir().join(format!(
+            "jisrot_partner_api_errors_{}.json",
+            std::process::id()
+        ));
+        std::fs::write(
+            &file_in,
+            r#"{"errors": [{"message": "Access denied"}], "data": null}"#,
+        )
+        .unwrap();
+        let result = read_events_from_partner_api_json(&file_in, SHOP_DOMAIN_FIELD);
+        std::fs::remove_file(&file_in).unwrap();
+
+        let error = result.unwrap_err();
+        assert!(error.to_string().contains("Access denied"));
+    }
+}
@@ -1,13 +1,4 @@
-pub const SUBSCRIPTION_CANCELED_STRINGS: [&str; 2] =
-    ["Subscription charge canceled", "Subscription charge frozen"];
-
-pub const SUBSCRIPTION_ACTIVATED_STRINGS: [&str; 2] = [
-    "Subscription charge activated",
-    "Subscription charge unfrozen",
-];
-
-pub const ONE_TIME_ACTIVATED_STRINGS: [&str; 1] = ["Charge activated"];
-
+//  Event names of categories are defined by event vocabularies, see `EventVocabulary`
 pub const INSTALLED_STRING: &str = "Installed";
 pub const UNINSTALLED_STRING: &str = "Uninstalled";
 pub const STORE_CLOSED_STRING: &str = "Store closed";
@@ -19,8 +10,34 @@ pub const UNINSTALLED_OLD_STRING: &str = "UninstalledOld";
 
 pub const SUBSCRIPTION_STATUS_CANCELED: &str = "Canceled";
 pub const SUBSCRIPTION_STATUS_ACTIVE: &str = "Active";
+pub const SUBSCRIPTION_STATUS_FROZEN: &str = "Frozen";
 // pub const SUBSCRIPTION_STATUS_CHANGED: &str = "Changed";
 
+pub const STORE_STATUS_OPEN: &str = "Open";
+pub const STORE_STATUS_CLOSED: &str = "Closed";
+pub const STORE_STATUS_REOPENED: &str = "Reopened";
+
+pub const TRIAL_STATUS_TRIALING: &str = "Trialing";
+pub const TRIAL_STATUS_CONVERTED: &str = "Converted";
+pub const TRIAL_STATUS_EXPIRED: &str = "Expired";
+pub const TRIAL_STATUS_UNINSTALLED: &str = "UninstalledInTrial";
+
+pub const CHURN_FORMULA_INSTALL_BASED: &str = "Install-based";
+pub const CHURN_FORMULA_NET_INSTALL_BASED: &str = "Net install-based";
+pub const CHURN_FORMULA_LOGO: &str = "Logo";
+pub const CHURN_FORMULA_REVENUE: &str = "Revenue";
+
+pub const OVERWRITE_POLICY_OVERWRITE: &str = "Overwrite";
+pub const OVERWRITE_POLICY_SKIP: &str = "Skip";
+pub const OVERWRITE_POLICY_SUFFIX: &str = "Add suffix";
+
+pub const REACTIVATION_KIND_REINSTALL: &str = "Reinstall";
+pub const REACTIVATION_KIND_RESUBSCRIBE: &str = "Resubscribe";
+
+/// Changing plan cancels the old subscription and activates the new one almost at once,
+/// so re-subscribing sooner than this after canceling is not a win-back
+pub const RESUBSCRIBE_MIN_GAP_HOURS: i64 = 1;
+
 pub const EVENT_FIELD: &str = "Event";
 pub const DETAILS_FIELD: &str = "Details";
 pub const TIME_FIELD: &str = "Date";
@@ -30,12 +47,239 @@ pub const SHOP_DOMAIN_FIELD: &str = "Shop domain";
 pub const SHOP_NAME_FIELD: &str = "Shop name";
 pub const SHOP_COUNTRY_FIELD: &str = "Shop country";
 
+pub const KNOWN_FIELDS: [&str; 8] = [
+    EVENT_FIELD,
+    DETAILS_FIELD,
+    TIME_FIELD,
+    EMAIL_FIELD,
+    BILLING_ON_FIELD,
+    SHOP_DOMAIN_FIELD,
+    SHOP_NAME_FIELD,
+    SHOP_COUNTRY_FIELD,
+];
+
+/// Event types of the Partner API `app { events }` query and their names in exported CSV
+pub const PARTNER_API_EVENT_TYPES: [(&str, &str); 21] = [
+    ("RELATIONSHIP_INSTALLED", INSTALLED_STRING),
+    ("RELATIONSHIP_UNINSTALLED", UNINSTALLED_STRING),
+    ("RELATIONSHIP_DEACTIVATED", STORE_CLOSED_STRING),
+    ("RELATIONSHIP_REACTIVATED", STORE_REOPENED_STRING),
+    (
+        "SUBSCRIPTION_CHARGE_ACCEPTED",
+        "Subscription charge accepted",
+    ),
+    (
+        "SUBSCRIPTION_CHARGE_ACTIVATED",
+        "Subscription charge activated",
+    ),
+    (
+        "SUBSCRIPTION_CHARGE_CANCELED",
+        "Subscription charge canceled",
+    ),
+    (
+        "SUBSCRIPTION_CHARGE_DECLINED",
+        "Subscription charge declined",
+    ),
+    ("SUBSCRIPTION_CHARGE_EXPIRED", "Subscription charge expired"),
+    ("SUBSCRIPTION_CHARGE_FROZEN", "Subscription charge frozen"),
+    (
+        "SUBSCRIPTION_CHARGE_UNFROZEN",
+        "Subscription charge unfrozen",
+    ),
+    (
+        "SUBSCRIPTION_APPROACHING_CAPPED_AMOUNT",
+        "Subscription approaching capped amount",
+    ),
+    (
+        "SUBSCRIPTION_CAPPED_AMOUNT_UPDATED",
+        "Subscription capped amount updated",
+    ),
+    ("ONE_TIME_CHARGE_ACCEPTED", "Charge accepted"),
+    ("ONE_TIME_CHARGE_ACTIVATED", "Charge activated"),
+    ("ONE_TIME_CHARGE_DECLINED", "Charge declined"),
+    ("ONE_TIME_CHARGE_EXPIRED", "Charge expired"),
+    ("USAGE_CHARGE_APPLIED", "Usage charge applied"),
+    ("CREDIT_APPLIED", "Credit applied"),
+    ("CREDIT_FAILED", "Credit failed"),
+    ("CREDIT_PENDING", "Credit pending"),
+];
+
+pub const PARTNER_API_ENDPOINT_PATTERN: &str =
+    "https://partners.shopify.com/{organization}/api/{version}/graphql.json";
+pub const PARTNER_API_VERSION: &str = "2025-10";
+pub const PARTNER_API_TOKEN_HEADER: &str = "X-Shopify-Access-Token";
+pub const PARTNER_API_TOKEN_ENV_VAR: &str = "JISROT_PARTNER_API_TOKEN";
+pub const PARTNER_API_APP_GID_PREFIX: &str = "gid://partners/App/";
+pub const PARTNER_API_THROTTLED_CODE: &str = "THROTTLED";
+pub const PARTNER_API_CHECKPOINT_SUFFIX: &str = ".checkpoint";
+pub const PARTNER_API_OUT_FILE_PREF: &str = "partner_api_events";
+pub const PARTNER_API_DEFAULT_PAGE_SIZE: u32 = 100;
+
+//  Partner API allows 4 requests per second
+pub const FETCH_REQUEST_INTERVAL_MS: u64 = 250;
+pub const FETCH_TIMEOUT_SECS: u64 = 60;
+pub const FETCH_MAX_RETRIES: u32 = 6;
+pub const FETCH_BACKOFF_BASE_MS: u64 = 1000;
+pub const FETCH_BACKOFF_MAX_MS: u64 = 60_000;
+
+pub const PARTNER_API_FILE_EXTENSION: &str = "json";
+
+//  Fields of Partner API events without a CSV column, kept as other fields
+pub const PARTNER_API_AMOUNT_FIELD: &str = "Amount";
+pub const PARTNER_API_CURRENCY_FIELD: &str = "Currency";
+pub const PARTNER_API_SHOP_ID_FIELD: &str = "Shop ID";
+pub const PARTNER_API_CHARGE_ID_FIELD: &str = "Charge ID";
+pub const PARTNER_API_TEST_FIELD: &str = "Test";
+
+pub const CLI_SUMMARY_COMMAND: &str = "summary";
+pub const CLI_ANALYZE_COMMAND: &str = "analyze";
+pub const CLI_FORMAT_OPTION: &str = "--format";
+pub const CLI_PRICING_OPTION: &str = "--pricing";
+pub const CLI_EXCLUDING_OPTION: &str = "--excluding";
+pub const CLI_PROFILE_OPTION: &str = "--profile";
+pub const CLI_VOCABULARY_OPTION: &str = "--vocabulary";
+pub const CLI_OUT_FOLDER_OPTION: &str = "--out";
+pub const CLI_FETCH_COMMAND: &str = "fetch";
+pub const CLI_ORGANIZATION_OPTION: &str = "--organization";
+pub const CLI_APP_OPTION: &str = "--app";
+pub const CLI_FROM_OPTION: &str = "--from";
+pub const CLI_TO_OPTION: &str = "--to";
+pub const CLI_TOKEN_OPTION: &str = "--token";
+pub const CLI_ENDPOINT_OPTION: &str = "--endpoint";
+pub const CLI_PAGE_SIZE_OPTION: &str = "--page-size";
+pub const CLI_OUT_FILE_OPTION: &str = "--out-file";
+pub const OUTPUT_FORMAT_SEPARATOR: char = ',';
+
+pub const OUTPUT_FORMAT_JSON: &str = "json";
+pub const OUTPUT_FORMAT_CSV: &str = "csv";
+pub const OUTPUT_FORMAT_XLSX: &str = "xlsx";
+pub const OUTPUT_FORMAT_HTML: &str = "html";
+pub const OUTPUT_FORMAT_TEMPLATE_REPORT: &str = "template_report";
+pub const OUTPUT_FORMAT_STATS_COMPARISON: &str = "stats_comparison";
+pub const OUTPUT_FORMAT_BREAKDOWN_CSV: &str = "breakdown_csv";
+
 pub const NONE: &str = "None";
+pub const YES: &str = "Yes";
+pub const NO: &str = "No";
 
 pub const EVENT_TIME_PATTERN: &str = "%Y-%m-%d %H:%M:%S UTC";
 pub const BILLING_ON_PATTERN: &str = "%Y-%m-%d";
 
+/// Date formats tried on event history columns, e.g. after re-saving CSV in a spreadsheet.
+/// Formats reading a column differently, e.g. month or day first, are rejected unless the input profile sets one
+pub const DETECTED_TIME_PATTERNS: [&str; 18] = [
+    EVENT_TIME_PATTERN,
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S%.fZ",
+    "%Y-%m-%dT%H:%M:%S%.f%:z",
+    "%Y-%m-%d %H:%M",
+    "%Y/%m/%d %H:%M:%S",
+    "%m/%d/%Y %H:%M:%S",
+    "%m/%d/%Y %H:%M",
+    "%d/%m/%Y %H:%M:%S",
+    "%d/%m/%Y %H:%M",
+    "%d.%m.%Y %H:%M:%S",
+    "%d.%m.%Y %H:%M",
+    BILLING_ON_PATTERN,
+    "%Y/%m/%d",
+    "%m/%d/%Y",
+    "%d/%m/%Y",
+    "%d.%m.%Y",
+    "%b %d, %Y",
+];
+
+/// Times in history database are stored in this pattern, so that text order is time order
+pub const HISTORY_TIME_PATTERN: &str = "%Y-%m-%d %H:%M:%S";
+pub const HISTORY_DATE_PATTERN: &str = "%Y-%m-%d";
+
+/// Short date of time strings in total stats, e.g. Jan05
+pub const SHORT_DATE_PATTERN: &str = "%b%d";
+pub const RUN_TIME_PATTERN: &str = "%Y%m%d-%H%M%S";
+pub const REPORT_TIME_PATTERN: &str = "%Y-%m-%d %H:%M";
+pub const TREND_LABEL_PATTERN: &str = "%m-%d";
+
 // pub const PAID_TYPE_FIELD_SUBSCRIPTIONS: &str = "subscriptions";
 // pub const PAID_TYPE_FIELD_ONE_TIME: &str = "one_times";
 
 pub const YEARLY_PATTERN: &str = "Year";
+
+pub const DAYS_PER_MONTH: f64 = 30.0;
+pub const DAYS_PER_YEAR: f64 = 365.0;
+pub const MONTHS_PER_YEAR: f64 = 12.0;
+pub const COHORT_PATTERN: &str = "%Y-%m";
+
+/// Placeholders of output file name template
+pub const NAME_PLACEHOLDER_DATA: &str = "{data}";
+pub const NAME_PLACEHOLDER_APP: &str = "{app}";
+pub const NAME_PLACEHOLDER_START: &str = "{start}";
+pub const NAME_PLACEHOLDER_END: &str = "{end}";
+pub const NAME_PLACEHOLDER_START_DATE: &str = "{start_date}";
+pub const NAME_PLACEHOLDER_END_DATE: &str = "{end_date}";
+pub const NAME_PLACEHOLDER_RUN: &str = "{run}";
+pub const NAME_SEPARATOR: char = '_';
+pub const DEFAULT_OUT_FILE_NAME_TEMPLATE: &str = "{app}_{data}_{start_date}_{end_date}";
+
+pub const DIMENSION_SEPARATOR: char = ',';
+pub const DIMENSION_COUNTRY: &str = "country";
+pub const DIMENSION_EMAIL_DOMAIN: &str = "email_domain";
+pub const DIMENSION_PLAN_AT_INSTALL: &str = "plan_at_install";
+pub const DIMENSION_EVER_SUBSCRIBED: &str = "ever_subscribed";
+pub const DIMENSION_SHOP_NAME_PREFIX: &str = "shop_name:";
+pub const DIMENSION_FIELD_PREFIX: &str = "field:";
+
+/// Histogram buckets of durations: label and upper bound in hours
+pub const DURATION_BUCKETS: [(&str, f64); 6] = [
+    ("< 1 hour", 1.0),
+    ("1-24 hours", 24.0),
+    ("1-7 days", 168.0),
+    ("7-30 days", 720.0),
+    ("30-90 days", 2160.0),
+    ("> 90 days", f64::INFINITY),
+];
+
+/// Total stats fields compared per plan instead of as plain metrics, the paying and free splits repeat the plans
+pub const COMPARISON_SKIPPED_FIELDS: [&str; 3] = [
+    "sub_stats_details.",
+    "paying_sub_stats_details.",
+    "free_sub_stats_details.",
+];
+
+pub const XLSX_SHEET_SUMMARY: &str = "Summary";
+pub const XLSX_SHEET_SUBSCRIPTIONS: &str = "Subscriptions";
+pub const XLSX_SHEET_ONE_TIMES: &str = "One-time packs";
+pub const XLSX_SHEET_MERCHANTS: &str = "Merchants";
+pub const XLSX_SHEET_APP_EVENTS: &str = "App Events";
+pub const XLSX_ALL_PACKS: &str = "All";
+
+pub const XLSX_INTEGER_FORMAT: &str = "#,##0";
+pub const XLSX_DECIMAL_FORMAT: &str = "#,##0.00";
+pub const XLSX_MONEY_FORMAT: &str = "#,##0.00";
+pub const XLSX_PERCENT_FORMAT: &str = "0.00%";
+pub const XLSX_DATETIME_FORMAT: &str = "yyyy-mm-dd hh:mm:ss";
+
+/// Fields holding a percentage (e.g. 12.5 for 12.5%)
+pub const XLSX_PERCENT_FIELDS: [&str; 6] = [
+    "total_churn_rate",
+    "churn_rate",
+    "headline_churn_rate",
+    "conversion_rate",
+    "logo_churn_rate",
+    "revenue_churn_rate",
+];
+
+/// Fields holding money contain one of these
+pub const XLSX_MONEY_FIELD_KEYWORDS: [&str; 5] = ["revenue", "mrr", "arpu", "ltv", "price"];
+
+/// Total stats fields detailed in their own sheets, or shown separately
+pub const XLSX_SUMMARY_SKIPPED_FIELDS: [&str; 10] = [
+    "start_time",
+    "end_time",
+    "start_time_str",
+    "end_time_str",
+    "one_time_details",
+    "one_time_summary",
+    "one_time_pack_stats",
+    "sub_stats_details",
+    "paying_sub_stats_details",
+    "free_sub_stats_details",
+];
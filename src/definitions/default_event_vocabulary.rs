@@ -0,0 +1,31 @@
+pub const SHOPIFY_EVENT_VOCABULARY_JSON_STRING: &str = r#"{
+    "categories": {
+        "installed": [
+            { "pattern": "Installed" }
+        ],
+        "uninstalled": [
+            { "pattern": "Uninstalled" }
+        ],
+        "store_closed": [
+            { "pattern": "Store closed" }
+        ],
+        "store_reopened": [
+            { "pattern": "Store re-opened" }
+        ],
+        "subscription_activated": [
+            { "pattern": "Subscription charge activated" }
+        ],
+        "subscription_canceled": [
+            { "pattern": "Subscription charge canceled" }
+        ],
+        "subscription_frozen": [
+            { "pattern": "Subscription charge frozen" }
+        ],
+        "subscription_unfrozen": [
+            { "pattern": "Subscription charge unfrozen" }
+        ],
+        "one_time_activated": [
+            { "pattern": "Charge activated" }
+        ]
+    }
+}"#;
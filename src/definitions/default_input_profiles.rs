@@ -0,0 +1,14 @@
+pub const SHOPIFY_INPUT_PROFILE_JSON_STRING: &str = r#"{
+    "columns": {
+        "Date": ["Date", "Time", "Occurred at"],
+        "Event": ["Event", "Event type"],
+        "Details": ["Details", "Description"],
+        "Billing on": ["Billing on", "Billing date"],
+        "Shop name": ["Shop name", "Store name"],
+        "Shop country": ["Shop country", "Country"],
+        "Shop email": ["Shop email", "Email"],
+        "Shop domain": ["Shop domain", "Domain", "myshopify domain"]
+    },
+    "time_formats": ["%Y-%m-%d %H:%M:%S UTC"],
+    "billing_on_formats": ["%Y-%m-%d"]
+}"#;
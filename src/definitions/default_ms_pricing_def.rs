@@ -46,8 +46,9 @@ pub const SPOP_PRICING_DEF_JSON_STRING: &str = "{
       \"code\": \"Free\",
       \"name\": \"Free\",
       \"regex_pattern\": \"Free\",
-      \"price\": 8.99,
-      \"currency\": \"USD\"
+      \"price\": 0.0,
+      \"currency\": \"USD\",
+      \"free\": true
     },
     {
       \"code\": \"Standard\",
@@ -0,0 +1,46 @@
+pub const SUMMARY_TXT_TEMPLATE_STRING: &str = r#"App stats {{ total_stats.start_time_str }} - {{ total_stats.end_time_str }}
+
+Installs: {{ total_stats.installed_count }}
+Uninstalls: {{ total_stats.uninstalled_count }}
+Merchant growth: {{ total_stats.merchant_growth }}
+New subscriptions: {{ total_stats.new_sub_count }} (paying: {{ total_stats.new_paying_sub_count }})
+Canceled subscriptions: {{ total_stats.canceled_sub_count }} (paying: {{ total_stats.canceled_paying_sub_count }})
+Paid growth: {{ total_stats.paid_growth }}
+One-time purchases: {{ total_stats.one_time_count }}
+Churn ({{ total_stats.headline_churn_formula }}): {{ total_stats.headline_churn_rate|round(2) }}%
+Estimated revenue: {{ total_stats.revenue_stats.total_revenue|round(2) }}
+
+Subscriptions by plan (new / canceled / growth):
+{% for plan, count in total_stats.sub_stats_details.new_sub.monthly_counts|items -%}
+- {{ plan }} monthly: {{ count }} / {{ total_stats.sub_stats_details.canceled_sub.monthly_counts[plan] }} / {{ total_stats.sub_stats_details.sub_growth.monthly_counts[plan] }}
+- {{ plan }} yearly: {{ total_stats.sub_stats_details.new_sub.yearly_counts[plan] }} / {{ total_stats.sub_stats_details.canceled_sub.yearly_counts[plan] }} / {{ total_stats.sub_stats_details.sub_growth.yearly_counts[plan] }}
+{% endfor %}
+One-time packs (purchases / revenue):
+{% for pack, stats in total_stats.one_time_pack_stats|items -%}
+- {{ pack }}: {{ stats.purchase_count }} / {{ stats.revenue|round(2) }}
+{% endfor -%}
+"#;
+
+pub const MERCHANTS_HTML_TEMPLATE_STRING: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Merchants {{ total_stats.start_time_str }} - {{ total_stats.end_time_str }}</title>
+<style>
+body { font-family: sans-serif; margin: 24px; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ddd; padding: 4px 10px; }
+th { background: #f5f5f5; }
+</style>
+</head>
+<body>
+<h1>Merchants {{ total_stats.start_time_str }} - {{ total_stats.end_time_str }}</h1>
+<table>
+<tr><th>Shop domain</th><th>Installed status</th><th>Subscription status</th><th>Plan</th><th>One-time purchases</th><th>Lifetime revenue</th></tr>
+{% for shop_domain, merchant in merchant_data.merchants|items -%}
+<tr><td>{{ merchant.shop_domain }}</td><td>{{ merchant.installed_status }}</td><td>{{ merchant.subscription_status }}</td><td>{{ merchant.last_new_sub_plan.code if merchant.last_new_sub_plan else "" }}</td><td>{{ merchant.one_time_count }}</td><td>{{ merchant.lifetime_revenue|round(2) }}</td></tr>
+{% endfor -%}
+</table>
+</body>
+</html>
+"#;
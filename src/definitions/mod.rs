@@ -1,4 +1,8 @@
 pub mod common;
+pub mod default_event_vocabulary;
+pub mod default_input_profiles;
 pub mod default_ms_excluding_def;
 pub mod default_ms_pricing_def;
+pub mod default_report_templates;
+pub mod partner_api_query;
 pub mod strings;
@@ -0,0 +1,114 @@
+/// Page of app events, the fields are the ones read by `read_events_from_partner_api_json`
+pub const APP_EVENTS_QUERY_STRING: &str = r#"query AppEvents(
+  $appId: ID!
+  $first: Int!
+  $after: String
+  $occurredAtMin: DateTime
+  $occurredAtMax: DateTime
+) {
+  app(id: $appId) {
+    events(
+      first: $first
+      after: $after
+      occurredAtMin: $occurredAtMin
+      occurredAtMax: $occurredAtMax
+    ) {
+      edges {
+        cursor
+        node {
+          type
+          occurredAt
+          shop {
+            id
+            name
+            myshopifyDomain
+          }
+          ... on RelationshipUninstalled {
+            reason
+            description
+          }
+          ... on SubscriptionChargeAccepted {
+            charge {
+              ...SubscriptionFields
+            }
+          }
+          ... on SubscriptionChargeActivated {
+            charge {
+              ...SubscriptionFields
+            }
+          }
+          ... on SubscriptionChargeCanceled {
+            charge {
+              ...SubscriptionFields
+            }
+          }
+          ... on SubscriptionChargeDeclined {
+            charge {
+              ...SubscriptionFields
+            }
+          }
+          ... on SubscriptionChargeExpired {
+            charge {
+              ...SubscriptionFields
+            }
+          }
+          ... on SubscriptionChargeFrozen {
+            charge {
+              ...SubscriptionFields
+            }
+          }
+          ... on SubscriptionChargeUnfrozen {
+            charge {
+              ...SubscriptionFields
+            }
+          }
+          ... on OneTimeChargeAccepted {
+            charge {
+              ...OneTimeFields
+            }
+          }
+          ... on OneTimeChargeActivated {
+            charge {
+              ...OneTimeFields
+            }
+          }
+          ... on OneTimeChargeDeclined {
+            charge {
+              ...OneTimeFields
+            }
+          }
+          ... on OneTimeChargeExpired {
+            charge {
+              ...OneTimeFields
+            }
+          }
+        }
+      }
+      pageInfo {
+        hasNextPage
+      }
+    }
+  }
+}
+
+fragment SubscriptionFields on AppSubscription {
+  id
+  name
+  billingOn
+  test
+  amount {
+    amount
+    currencyCode
+  }
+}
+
+fragment OneTimeFields on AppPurchaseOneTime {
+  id
+  name
+  test
+  amount {
+    amount
+    currencyCode
+  }
+}
+"#;
@@ -2,23 +2,139 @@ pub mod message {
     pub mod success {
         pub const _TITLE: &str = "Success";
         pub const SPECIFIC_DATA_WRITTEN_FILE: &str = "is written to file";
+        pub const SPECIFIC_DATA_SKIPPED_FILE: &str = "is skipped, already exists file";
+        pub const EVENTS_IMPORTED_HISTORY: &str = "Events are imported to history database of app";
+        pub const COPIED_CLIPBOARD: &str = "is copied to clipboard";
+        pub const FETCH_RESUMED_CHECKPOINT: &str = "Fetch is resumed from checkpoint after page";
+    }
+    pub mod warning {
+        pub const EVENT_WITHOUT_TIME_NOT_KEPT: &str =
+            "Event without time is not kept in history database";
     }
     pub mod error {
         pub const _TITLE: &str = "Error";
         pub const FILE_NOT_CHOSEN: &str = "File not chosen";
+        pub const EMPTY_DATA: &str = "is empty";
+        pub const INVALID_DIMENSION: &str = "Invalid breakdown dimension";
+        pub const INVALID_DATE: &str = "Invalid date";
+        pub const INVALID_OUTPUT_FORMAT: &str = "Unknown output format";
+        pub const INVALID_EVENT_PATTERN: &str = "Invalid event name pattern";
+        pub const NO_DATE_FORMAT_MATCHED: &str =
+            "No known date format matches all values of column";
+        pub const AMBIGUOUS_DATE_FORMAT: &str =
+            "Date formats read values differently, set the format in the input profile, column";
+        pub const COMPARISON_FILES_NOT_CHOSEN: &str = "Two files to compare not chosen";
+        pub const SUMMARY_FILES_NOT_CHOSEN: &str = "One or two files to summarize not chosen";
+        pub const CLI_SUMMARY_USAGE: &str =
+            "Usage: jisrot summary <total_stats.json> [<previous_total_stats.json>]";
+        pub const PARTNER_API_TOKEN_MISSING: &str =
+            "Partner API access token not given, use --token or JISROT_PARTNER_API_TOKEN";
+        pub const PARTNER_API_REQUEST_FAILED: &str = "Partner API request failed";
+        pub const PARTNER_API_RETRIES_EXHAUSTED: &str =
+            "Partner API is still rate limited after retries";
+        pub const CLI_FETCH_USAGE: &str = "Usage: jisrot fetch (--organization <id> | --endpoint <url>) --app <id> [--from <YYYY-MM-DD>] [--to <YYYY-MM-DD>] [--token <token>] [--page-size <n>] [--out-file <file.json>]";
+        pub const CLI_ANALYZE_USAGE: &str = "Usage: jisrot analyze [--format csv,xlsx,html] [--pricing <preset or file>] [--excluding <preset or file>] [--profile <preset or file>] [--vocabulary <preset or file>] [--out <folder>] <events.csv or Partner API events.json>...";
     }
 }
 
 pub mod data {
     pub const OUT_FOLDER_NAME: &str = "Output";
+    pub const HISTORY_DB_FILE_NAME: &str = "history.sqlite";
 
     pub const _KIND_PREDEFINED: &str = "Pre-defined";
     pub const KIND_CUSTOM: &str = "Custom";
     pub const TOTAL_STATS: &str = "Total Stats";
     pub const MERCHANT_DATA: &str = "Merchant Data";
     pub const APP_EVENTS: &str = "App Event List";
+    pub const BREAKDOWN_STATS: &str = "Breakdown Stats";
+    pub const HISTORY_APP: &str = "History app";
+    pub const STATS_COMPARISON: &str = "Stats Comparison";
+    pub const REPORT: &str = "Report";
+    pub const HTML_REPORT: &str = "HTML Report";
+    pub const MARKDOWN_SUMMARY: &str = "Markdown Summary";
+    pub const TEMPLATE_REPORT: &str = "Template Report";
+    pub const PARTNER_API_EVENTS: &str = "Partner API Events";
+    pub const REPORT_TEMPLATE: &str = "Report template";
     pub const PRICING_DEFS: &str = "Pricing definitions";
     pub const EXCLUDING_DEFS: &str = "Excluding definitions";
+    pub const INPUT_PROFILE: &str = "Input profile";
+    pub const EVENT_VOCABULARY: &str = "Event vocabulary";
+}
+
+pub mod report {
+    pub const TITLE: &str = "App Stats Report";
+    pub const SUMMARY_TITLE: &str = "App Stats";
+    pub const SUMMARY_COMPARED_WITH: &str = "Compared with";
+
+    pub const SECTION_HEADLINE: &str = "Headline";
+    pub const SECTION_TRENDS: &str = "Trends";
+    pub const SECTION_SUBSCRIPTIONS: &str = "Subscriptions by plan";
+    pub const SECTION_MONTHLY_SUBSCRIPTIONS: &str = "Monthly subscriptions";
+    pub const SECTION_YEARLY_SUBSCRIPTIONS: &str = "Yearly subscriptions";
+    pub const SECTION_ONE_TIMES: &str = "One-time packs";
+    pub const SECTION_CHURN: &str = "Churn";
+
+    pub const CARD_INSTALLED: &str = "Installs";
+    pub const CARD_UNINSTALLED: &str = "Uninstalls";
+    pub const CARD_MERCHANT_GROWTH: &str = "Merchant growth";
+    pub const CARD_STORE_CLOSURE_GROWTH: &str = "Store closure growth";
+    pub const CARD_NEW_SUB: &str = "New subscriptions";
+    pub const CARD_CANCELED_SUB: &str = "Canceled subscriptions";
+    pub const CARD_NEW_PAYING_SUB: &str = "New paying subscriptions";
+    pub const CARD_CANCELED_PAYING_SUB: &str = "Canceled paying subscriptions";
+    pub const CARD_PAID_GROWTH: &str = "Paid growth";
+    pub const CARD_FROZEN_SUB: &str = "Frozen subscriptions";
+    pub const CARD_UNFROZEN_SUB: &str = "Unfrozen subscriptions";
+    pub const CARD_FROZEN_THEN_CANCELED_SUB: &str = "Frozen then canceled";
+    pub const CARD_ONE_TIME: &str = "One-time purchases";
+    pub const CARD_HEADLINE_CHURN: &str = "Churn";
+    pub const CARD_REVENUE: &str = "Estimated revenue";
+
+    pub const TREND_DAILY: &str = "Events per day";
+    pub const TREND_WEEKLY: &str = "Events per week (weeks start on Monday)";
+    pub const SERIES_INSTALLED: &str = "Installed";
+    pub const SERIES_UNINSTALLED: &str = "Uninstalled";
+    pub const SERIES_NEW_SUB: &str = "Subscription activated";
+    pub const SERIES_CANCELED_SUB: &str = "Subscription canceled";
+
+    pub const COL_METRIC: &str = "Metric";
+    pub const COL_VALUE: &str = "Value";
+    pub const COL_CURRENT: &str = "Current";
+    pub const COL_PREVIOUS: &str = "Previous";
+    pub const COL_CHANGE: &str = "Change";
+
+    pub const COL_PLAN: &str = "Plan";
+    pub const COL_NEW: &str = "New";
+    pub const COL_CANCELED: &str = "Canceled";
+    pub const COL_GROWTH: &str = "Growth";
+    pub const COL_GROWTH_VS_PREVIOUS: &str = "Growth vs previous";
+    pub const COL_PAYING: &str = "Paying";
+    pub const COL_NEW_MONTHLY: &str = "New monthly";
+    pub const COL_NEW_YEARLY: &str = "New yearly";
+    pub const COL_CANCELED_MONTHLY: &str = "Canceled monthly";
+    pub const COL_CANCELED_YEARLY: &str = "Canceled yearly";
+    pub const COL_GROWTH_MONTHLY: &str = "Growth monthly";
+    pub const COL_GROWTH_YEARLY: &str = "Growth yearly";
+
+    pub const COL_PACK: &str = "Pack";
+    pub const COL_PURCHASES: &str = "Purchases";
+    pub const COL_REVENUE: &str = "Revenue";
+    pub const COL_BUYERS: &str = "Buyers";
+    pub const COL_REPEAT_BUYERS: &str = "Repeat buyers";
+    pub const COL_AVERAGE_PACKS_PER_BUYER: &str = "Packs per buyer";
+    pub const COL_PURCHASES_VS_PREVIOUS: &str = "Purchases vs previous";
+    pub const ROW_ALL_PACKS: &str = "All packs";
+
+    pub const COL_FORMULA: &str = "Formula";
+    pub const COL_RATE: &str = "Rate";
+    pub const COL_PERIOD_START: &str = "Period start";
+    pub const COL_PERIOD_END: &str = "Period end";
+    pub const COL_STARTING: &str = "Starting";
+    pub const COL_CHURNED: &str = "Churned";
+    pub const ROW_ACTIVE_MERCHANTS: &str = "Active merchants";
+    pub const ROW_SUBSCRIBED_MERCHANTS: &str = "Subscribed merchants";
+    pub const ROW_CHURNED_UNINSTALLED: &str = "Lost by uninstall";
+    pub const ROW_CHURNED_STORE_CLOSED: &str = "Lost by store closure";
 }
 
 pub mod ui {
@@ -26,23 +142,60 @@ pub mod ui {
 
     use crate::{
         definitions::{
+            default_event_vocabulary::SHOPIFY_EVENT_VOCABULARY_JSON_STRING,
+            default_input_profiles::SHOPIFY_INPUT_PROFILE_JSON_STRING,
             default_ms_excluding_def::MS_EXCLUDING_DEF_JSON_STRING,
             default_ms_pricing_def::{SBM_PRICING_DEF_JSON_STRING, SPOP_PRICING_DEF_JSON_STRING},
+            default_report_templates::{
+                MERCHANTS_HTML_TEMPLATE_STRING, SUMMARY_TXT_TEMPLATE_STRING,
+            },
         },
         models::ui_model::*,
     };
 
     pub const BTN_BROWSE_LBL: &str = "Browse...";
+    pub const BTN_CLEAR_LBL: &str = "Clear";
     pub const BTN_ANALYZE_LBL: &str = "Analyze!";
+    pub const BTN_ANALYZE_HISTORY_LBL: &str = "Analyze history!";
+    pub const BTN_SUMMARY_FILE_PICKER_LBL: &str = "Copy Markdown summary...";
+    pub const BTN_COMPARE_FILE_PICKER_LBL: &str = "Compare two total stats files...";
     pub const BTN_EVENT_FILE_PICKER_LBL: &str = "Browse event history file...";
 
     pub const _DEFAULT_SELECTOR_TEXT: &str = "- Please select -";
 
     pub const SELECTOR_PRICING_DEFS_ID: &str = "selector_pricing_defs";
     pub const SELECTOR_EXCLUDING_DEFS_ID: &str = "selector_excluding_defs";
+    pub const SELECTOR_INPUT_PROFILE_ID: &str = "selector_input_profile";
+    pub const SELECTOR_EVENT_VOCABULARY_ID: &str = "selector_event_vocabulary";
 
     pub const CHECKBOX_DEBUG_MODE_LBL: &str = "Debug mode";
     pub const CHECKBOX_CASE_SENSITIVE_REGEX_LBL: &str = "Case-sensitive regex";
+    pub const CHECKBOX_GROUP_EXPORT_LBL: &str = "Also export:";
+    pub const SELECTOR_CHURN_FORMULA_ID: &str = "selector_churn_formula";
+    pub const SELECTOR_CHURN_FORMULA_LBL: &str = "Headline churn:";
+    pub const SELECTOR_REPORT_TEMPLATE_ID: &str = "selector_report_template";
+    pub const SELECTOR_REPORT_TEMPLATE_LBL: &str = "Report template:";
+
+    pub const INPUT_BREAKDOWN_DIMENSIONS_LBL: &str = "Breakdown by:";
+    pub const INPUT_BREAKDOWN_DIMENSIONS_HINT: &str =
+        "e.g. country, email_domain, ever_subscribed, field:<column>";
+
+    pub const INPUT_OPENING_STATE_LBL: &str = "Opening state:";
+    pub const INPUT_OPENING_STATE_HINT: &str = "None (merchant data of previous period)";
+
+    pub const INPUT_OUT_FOLDER_LBL: &str = "Output folder:";
+    pub const INPUT_OUT_FOLDER_HINT: &str = "Output (in current folder)";
+    pub const INPUT_OUT_FILE_NAME_TEMPLATE_LBL: &str = "File names:";
+    pub const INPUT_OUT_FILE_NAME_TEMPLATE_TOOLTIP: &str = "Placeholders: {data}, {app} (history app or pricing preset), {start_date}, {end_date} (YYYY-MM-DD), {start}, {end} (e.g. Jan05), {run} (run time)";
+    pub const SELECTOR_OVERWRITE_POLICY_ID: &str = "selector_overwrite_policy";
+    pub const SELECTOR_OVERWRITE_POLICY_LBL: &str = "If exists:";
+
+    pub const INPUT_HISTORY_APP_LBL: &str = "History app:";
+    pub const INPUT_HISTORY_APP_HINT: &str = "App key, empty to not keep history";
+    pub const INPUT_HISTORY_START_DATE_LBL: &str = "From:";
+    pub const INPUT_HISTORY_END_DATE_LBL: &str = "To:";
+    pub const INPUT_HISTORY_DATE_HINT: &str = "YYYY-MM-DD";
+    pub const INPUT_COMPARISON_START_DATE_LBL: &str = "Compare with - From:";
 
     pub const EXCLUDING_DEFS_OPTION_MS: UiOption = UiOption {
         value: Cow::Borrowed("magestore"),
@@ -50,6 +203,18 @@ pub mod ui {
         connected_data: Some(Cow::Borrowed(MS_EXCLUDING_DEF_JSON_STRING)),
     };
 
+    pub const INPUT_PROFILE_OPTION_SHOPIFY: UiOption = UiOption {
+        value: Cow::Borrowed("shopify"),
+        text: Cow::Borrowed("Shopify export"),
+        connected_data: Some(Cow::Borrowed(SHOPIFY_INPUT_PROFILE_JSON_STRING)),
+    };
+
+    pub const EVENT_VOCABULARY_OPTION_SHOPIFY: UiOption = UiOption {
+        value: Cow::Borrowed("shopify"),
+        text: Cow::Borrowed("Shopify"),
+        connected_data: Some(Cow::Borrowed(SHOPIFY_EVENT_VOCABULARY_JSON_STRING)),
+    };
+
     pub const PRICING_DEFS_OPTION_SBM: UiOption = UiOption {
         value: Cow::Borrowed("sbm"),
         text: Cow::Borrowed("MS Barcode"),
@@ -62,6 +227,25 @@ pub mod ui {
         connected_data: Some(Cow::Borrowed(SPOP_PRICING_DEF_JSON_STRING)),
     };
 
+    /// Template option values are template names, their extension is the output file extension
+    pub const REPORT_TEMPLATE_OPTION_NONE: UiOption = UiOption {
+        value: Cow::Borrowed("none"),
+        text: Cow::Borrowed("None"),
+        connected_data: None,
+    };
+
+    pub const REPORT_TEMPLATE_OPTION_SUMMARY_TXT: UiOption = UiOption {
+        value: Cow::Borrowed("summary.txt"),
+        text: Cow::Borrowed("Text summary"),
+        connected_data: Some(Cow::Borrowed(SUMMARY_TXT_TEMPLATE_STRING)),
+    };
+
+    pub const REPORT_TEMPLATE_OPTION_MERCHANTS_HTML: UiOption = UiOption {
+        value: Cow::Borrowed("merchants.html"),
+        text: Cow::Borrowed("Merchant table (HTML)"),
+        connected_data: Some(Cow::Borrowed(MERCHANTS_HTML_TEMPLATE_STRING)),
+    };
+
     pub const OPTION_CUSTOM: UiOption = UiOption {
         value: Cow::Borrowed("custom"),
         text: Cow::Borrowed("Custom"),
@@ -70,6 +254,16 @@ pub mod ui {
 
     pub const EXCLUDING_DEFS_OPTION_LIST: [UiOption; 1] = [EXCLUDING_DEFS_OPTION_MS];
 
+    pub const INPUT_PROFILE_OPTION_LIST: [UiOption; 1] = [INPUT_PROFILE_OPTION_SHOPIFY];
+
+    pub const EVENT_VOCABULARY_OPTION_LIST: [UiOption; 1] = [EVENT_VOCABULARY_OPTION_SHOPIFY];
+
     pub const PRICING_DEFS_OPTION_LIST: [UiOption; 2] =
         [PRICING_DEFS_OPTION_SBM, PRICING_DEFS_OPTION_SPOP];
+
+    pub const REPORT_TEMPLATE_OPTION_LIST: [UiOption; 3] = [
+        REPORT_TEMPLATE_OPTION_NONE,
+        REPORT_TEMPLATE_OPTION_SUMMARY_TXT,
+        REPORT_TEMPLATE_OPTION_MERCHANTS_HTML,
+    ];
 }
@@ -0,0 +1,455 @@
+use anyhow::anyhow;
+use chrono::NaiveDate;
+use serde_json::{Value, json};
+use std::path::PathBuf;
+use std::thread::sleep;
+use std::time::Duration;
+
+use crate::data_io::*;
+use crate::definitions::common::*;
+use crate::definitions::partner_api_query::APP_EVENTS_QUERY_STRING;
+use crate::definitions::strings::*;
+use crate::models::data_model::*;
+
+/// Fetch app events page by page to the out file as a list of Partner API responses,
+/// readable by `read_events_from_partner_api_json`.
+/// Progress is saved after each page, an interrupted fetch of the same query resumes from it
+pub fn fetch_events(fetch: &PartnerApiFetch) -> anyhow::Result<String> {
+    let checkpoint_file: PathBuf = fetch.checkpoint_file();
+    let mut message_success = String::new();
+
+    let saved_checkpoint: Option<FetchCheckpoint> =
+        if checkpoint_file.exists() && fetch.out_file().exists() {
+            Some(read_fetch_checkpoint_from_json(&checkpoint_file)?).filter(|c| c.matches(fetch))
+        } else {
+            None
+        };
+
+    let (mut checkpoint, mut pages): (FetchCheckpoint, Vec<Value>) = match saved_checkpoint {
+        Some(checkpoint) => {
+            message_success += format!(
+                "{} {}\n",
+                message::success::FETCH_RESUMED_CHECKPOINT,
+                checkpoint.page_count()
+            )
+            .as_str();
+            //  Pages written after the last checkpoint are fetched again
+            let mut pages: Vec<Value> = read_partner_api_pages(fetch.out_file())?;
+            pages.truncate(*checkpoint.page_count() as usize);
+            (checkpoint, pages)
+        }
+        None => (FetchCheckpoint::new(fetch), Vec::new()),
+    };
+
+    if let Some(out_folder) = fetch.out_file().parent() {
+        std::fs::create_dir_all(out_folder)?;
+    }
+
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .http_status_as_error(false)
+        .timeout_global(Some(Duration::from_secs(FETCH_TIMEOUT_SECS)))
+        .build()
+        .into();
+
+    loop {
+        let variables = json!({
+            "appId": fetch.app_id(),
+            "first": fetch.page_size(),
+            "after": checkpoint.cursor(),
+            "occurredAtMin": fetch.occurred_at_min().map(|d| format_range_bound(d, false)),
+            "occurredAtMax": fetch.occurred_at_max().map(|d| format_range_bound(d, true)),
+        });
+
+        let page: Value = request_page(&agent, fetch, &variables)?;
+        let events = &page["data"]["app"]["events"];
+        let edges: Vec<Value> = events["edges"].as_array().cloned().unwrap_or_default();
+        let has_next_page: bool = events["pageInfo"]["hasNextPage"].as_bool().unwrap_or(false);
+
+        if let Some(cursor) = edges.last().and_then(|e| e["cursor"].as_str()) {
+            checkpoint.set_cursor(Some(cursor.to_string()));
+        }
+        checkpoint.set_page_count(checkpoint.page_count() + 1);
+        checkpoint.set_event_count(checkpoint.event_count() + edges.len() as u32);
+        pages.push(page);
+
+        //  Events first, a checkpoint never points past stored events
+        write_to_json(fetch.out_file(), &pages)?;
+        write_to_json(&checkpoint_file, &checkpoint)?;
+
+        if !has_next_page || edges.is_empty() {
+            break;
+        }
+        sleep(Duration::from_millis(FETCH_REQUEST_INTERVAL_MS));
+    }
+
+    std::fs::remove_file(&checkpoint_file)?;
+
+    message_success += format!(
+        "{} ({}) {} {}",
+        data::PARTNER_API_EVENTS,
+        checkpoint.event_count(),
+        message::success::SPECIFIC_DATA_WRITTEN_FILE,
+        fetch.out_file().display()
+    )
+    .as_str();
+
+    Ok(message_success)
+}
+
+/// Response of a page query, retried with back-off while rate limited or on server errors
+fn request_page(
+    agent: &ureq::Agent,
+    fetch: &PartnerApiFetch,
+    variables: &Value,
+) -> anyhow::Result<Value> {
+    let body = json!({
+        "query": APP_EVENTS_QUERY_STRING,
+        "variables": variables,
+    });
+
+    for attempt in 0..=FETCH_MAX_RETRIES {
+        let mut response = match agent
+            .post(fetch.endpoint())
+            .header(PARTNER_API_TOKEN_HEADER, fetch.access_token())
+            .send_json(&body)
+        {
+            Ok(response) => response,
+            Err(e) if attempt < FETCH_MAX_RETRIES => {
+                eprintln!(
+                    "{}: {}, retrying...",
+                    message::error::PARTNER_API_REQUEST_FAILED,
+                    e
+                );
+                sleep(backoff_delay(attempt, None));
+                continue;
+            }
+            Err(e) => {
+                return Err(anyhow!(
+                    "{}: {}",
+                    message::error::PARTNER_API_REQUEST_FAILED,
+                    e
+                ));
+            }
+        };
+
+        let status: u16 = response.status().as_u16();
+        if status == 429 || status >= 500 {
+            let retry_after: Option<u64> = response
+                .headers()
+                .get("Retry-After")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse().ok());
+            sleep(backoff_delay(attempt, retry_after));
+            continue;
+        }
+
+        let response_text: String = response.body_mut().read_to_string()?;
+        if !(200..300).contains(&status) {
+            return Err(anyhow!(
+                "{} ({}): {}",
+                message::error::PARTNER_API_REQUEST_FAILED,
+                status,
+                response_text
+            ));
+        }
+
+        let page: Value = serde_json::from_str(&response_text)?;
+        let errors: Vec<Value> = page["errors"].as_array().cloned().unwrap_or_default();
+
+        if errors
+            .iter()
+            .any(|e| e["extensions"]["code"].as_str() == Some(PARTNER_API_THROTTLED_CODE))
+        {
+            sleep(backoff_delay(attempt, None));
+            continue;
+        }
+
+        if !errors.is_empty() {
+            return Err(anyhow!(
+                "{}: {}",
+                message::error::PARTNER_API_REQUEST_FAILED,
+                errors
+                    .iter()
+                    .map(|e| e["message"].as_str().unwrap_or_default())
+                    .collect::<Vec<&str>>()
+                    .join("; ")
+            ));
+        }
+
+        return Ok(page);
+    }
+
+    Err(anyhow!(message::error::PARTNER_API_RETRIES_EXHAUSTED))
+}
+
+/// Exponential delay of an attempt, or the delay asked by the server
+fn backoff_delay(attempt: u32, retry_after_secs: Option<u64>) -> Duration {
+    let delay_ms: u64 = match retry_after_secs {
+        Some(secs) => secs * 1000,
+        None => FETCH_BACKOFF_BASE_MS.saturating_mul(1 << attempt.min(16)),
+    };
+    Duration::from_millis(delay_ms.min(FETCH_BACKOFF_MAX_MS))
+}
+
+/// Start or end of a range date in UTC, both bounds are inclusive
+fn format_range_bound(date: NaiveDate, is_end: bool) -> String {
+    if is_end {
+        format!("{}T23:59:59Z", date.format(HISTORY_DATE_PATTERN))
+    } else {
+        format!("{}T00:00:00Z", date.format(HISTORY_DATE_PATTERN))
+    }
+}
+
+pub fn fetch_from_cli(args: &[String]) -> anyhow::Result<String> {
+    let mut organization: Option<String> = None;
+    let mut endpoint: Option<String> = None;
+    let mut app_id: Option<String> = None;
+    let mut occurred_at_min: Option<NaiveDate> = None;
+    let mut occurred_at_max: Option<NaiveDate> = None;
+    let mut access_token: Option<String> = std::env::var(PARTNER_API_TOKEN_ENV_VAR).ok();
+    let mut page_size: u32 = PARTNER_API_DEFAULT_PAGE_SIZE;
+    let mut out_file: Option<PathBuf> = None;
+
+    let parse_date = |date_str: &str| {
+        NaiveDate::parse_from_str(date_str.trim(), HISTORY_DATE_PATTERN).map_err(|_| {
+            anyhow!(
+                "{}: {} ({})",
+                message::error::INVALID_DATE,
+                date_str,
+                HISTORY_DATE_PATTERN
+            )
+        })
+    };
+
+    let mut args_iter = args.iter();
+    while let Some(arg) = args_iter.next() {
+        let mut option_value = || {
+            args_iter
+                .next()
+                .cloned()
+                .ok_or(anyhow!(message::error::CLI_FETCH_USAGE))
+        };
+        match arg.as_str() {
+            CLI_ORGANIZATION_OPTION => organization = Some(option_value()?),
+            CLI_ENDPOINT_OPTION => endpoint = Some(option_value()?),
+            CLI_APP_OPTION => app_id = Some(option_value()?),
+            CLI_FROM_OPTION => occurred_at_min = Some(parse_date(&option_value()?)?),
+            CLI_TO_OPTION => occurred_at_max = Some(parse_date(&option_value()?)?),
+            CLI_TOKEN_OPTION => access_token = Some(option_value()?),
+            CLI_PAGE_SIZE_OPTION => {
+                page_size = option_value()?
+                    .parse()
+                    .map_err(|_| anyhow!(message::error::CLI_FETCH_USAGE))?
+            }
+            CLI_OUT_FILE_OPTION => out_file = Some(PathBuf::from(option_value()?)),
+            _ => return Err(anyhow!(message::error::CLI_FETCH_USAGE)),
+        }
+    }
+
+    let endpoint: String = match (endpoint, organization) {
+        (Some(endpoint), _) => endpoint,
+        (None, Some(organization)) => PARTNER_API_ENDPOINT_PATTERN
+            .replace("{organization}", &organization)
+            .replace("{version}", PARTNER_API_VERSION),
+        (None, None) => return Err(anyhow!(message::error::CLI_FETCH_USAGE)),
+    };
+    let app_id: String = app_id.ok_or(anyhow!(message::error::CLI_FETCH_USAGE))?;
+    let access_token: String =
+        access_token.ok_or(anyhow!(message::error::PARTNER_API_TOKEN_MISSING))?;
+
+    //  Default file of the app in the output folder, the same file resumes the same query
+    let out_file: PathBuf = match out_file {
+        Some(f) => f,
+        None => std::env::current_dir()?
+            .join(data::OUT_FOLDER_NAME)
+            .join(format!(
+                "{}_{}.{}",
+                PARTNER_API_OUT_FILE_PREF,
+                app_id.trim_start_matches(PARTNER_API_APP_GID_PREFIX),
+                PARTNER_API_FILE_EXTENSION
+            )),
+    };
+
+    fetch_events(&PartnerApiFetch::new(
+        &endpoint,
+        &access_token,
+        &app_id,
+        occurred_at_min,
+        occurred_at_max,
+        page_size,
+        &out_file,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    /// Partner API on a local port, answering the given (status, headers, body) in order, one per connection.
+    /// Joining the handle gives the request bodies
+    fn mock_partner_api(
+        responses: Vec<(u16, &'static str, String)>,
+    ) -> (String, JoinHandle<Vec<Value>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/graphql.json", listener.local_addr().unwrap());
+
+        let handle = std::thread::spawn(move || {
+            let mut requests: Vec<Value> = Vec::new();
+            for (status, headers, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut content_length: usize = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == "\r\n" {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':')
+                        && name.eq_ignore_ascii_case("content-length")
+                    {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+                let mut request_body = vec![0; content_length];
+                reader.read_exact(&mut request_body).unwrap();
+                requests.push(serde_json::from_slice(&request_body).unwrap());
+
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+                    status,
+                    body.len(),
+                    headers,
+                    body
+                )
+                .unwrap();
+            }
+            requests
+        });
+
+        (endpoint, handle)
+    }
+
+    fn events_page(cursors: &[&str], has_next_page: bool) -> String {
+        json!({
+            "data": { "app": { "events": {
+                "edges": cursors
+                    .iter()
+                    .map(|cursor| json!({
+                        "cursor": cursor,
+                        "node": { "type": "RELATIONSHIP_INSTALLED", "occurredAt": "2025-01-01T00:00:00Z" },
+                    }))
+                    .collect::<Vec<Value>>(),
+                "pageInfo": { "hasNextPage": has_next_page },
+            }}}
+        })
+        .to_string()
+    }
+
+    fn test_fetch(endpoint: &str, name: &str) -> PartnerApiFetch {
+        let out_file =
+            std::env::temp_dir().join(format!("jisrot_fetch_{}_{}.json", name, std::process::id()));
+        PartnerApiFetch::new(endpoint, "token", "1234", None, None, 2, &out_file)
+    }
+
+    #[test]
+    fn fetch_follows_cursor_over_pages() {
+        let (endpoint, server) = mock_partner_api(vec![
+            (200, "", events_page(&["c1", "c2"], true)),
+            (200, "", events_page(&["c3"], false)),
+        ]);
+        let fetch = test_fetch(&endpoint, "pages");
+
+        let message_success = fetch_events(&fetch).unwrap();
+        let requests = server.join().unwrap();
+        let pages = read_partner_api_pages(fetch.out_file()).unwrap();
+        std::fs::remove_file(fetch.out_file()).unwrap();
+
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0]["variables"]["appId"], "gid://partners/App/1234");
+        assert_eq!(requests[0]["variables"]["after"], Value::Null);
+        assert_eq!(requests[1]["variables"]["after"], "c2");
+        assert_eq!(pages.len(), 2);
+        assert!(message_success.contains("(3)"));
+        assert!(!fetch.checkpoint_file().exists());
+    }
+
+    #[test]
+    fn fetch_retries_after_rate_limit() {
+        let (endpoint, server) = mock_partner_api(vec![
+            (429, "Retry-After: 0\r\n", String::new()),
+            (200, "", events_page(&["c1"], false)),
+        ]);
+        let fetch = test_fetch(&endpoint, "rate_limit");
+
+        let result = fetch_events(&fetch);
+        let requests = server.join().unwrap();
+        let pages = read_partner_api_pages(fetch.out_file()).unwrap();
+        std::fs::remove_file(fetch.out_file()).unwrap();
+
+        result.unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(pages.len(), 1);
+    }
+
+    #[test]
+    fn fetch_retries_throttled_query() {
+        let throttled = json!({
+            "errors": [{ "message": "Throttled", "extensions": { "code": PARTNER_API_THROTTLED_CODE } }]
+        });
+        let (endpoint, server) = mock_partner_api(vec![
+            (200, "", throttled.to_string()),
+            (200, "", events_page(&["c1"], false)),
+        ]);
+        let fetch = test_fetch(&endpoint, "throttled");
+
+        let result = fetch_events(&fetch);
+        let requests = server.join().unwrap();
+        let pages = read_partner_api_pages(fetch.out_file()).unwrap();
+        std::fs::remove_file(fetch.out_file()).unwrap();
+
+        result.unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(pages.len(), 1);
+        assert!(pages[0].get("errors").is_none());
+    }
+
+    #[test]
+    fn fetch_resumes_from_checkpoint() {
+        let (endpoint, server) = mock_partner_api(vec![(200, "", events_page(&["c3"], false))]);
+        let fetch = test_fetch(&endpoint, "resume");
+
+        //  The second page was written before the fetch stopped, but not its checkpoint
+        let stored_pages: Vec<Value> = vec![
+            serde_json::from_str(&events_page(&["c1", "c2"], true)).unwrap(),
+            serde_json::from_str(&events_page(&["c3"], false)).unwrap(),
+        ];
+        let mut checkpoint = FetchCheckpoint::new(&fetch);
+        checkpoint.set_cursor(Some("c2".to_string()));
+        checkpoint.set_page_count(1);
+        checkpoint.set_event_count(2);
+        write_to_json(fetch.out_file(), &stored_pages).unwrap();
+        write_to_json(&fetch.checkpoint_file(), &checkpoint).unwrap();
+
+        let message_success = fetch_events(&fetch).unwrap();
+        let requests = server.join().unwrap();
+        let pages = read_partner_api_pages(fetch.out_file()).unwrap();
+        std::fs::remove_file(fetch.out_file()).unwrap();
+
+        assert!(message_success.starts_with(message::success::FETCH_RESUMED_CHECKPOINT));
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0]["variables"]["after"], "c2");
+        assert_eq!(pages.len(), 2);
+        assert_eq!(
+            pages[1]["data"]["app"]["events"]["edges"][0]["cursor"],
+            "c3"
+        );
+        assert!(message_success.contains("(3)"));
+        assert!(!fetch.checkpoint_file().exists());
+    }
+}
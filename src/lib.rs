@@ -0,0 +1,11 @@
+//! Analysis of Shopify app event history.
+//! Output formats can be added with `output_writers::register_output_writer`
+
+pub mod analyzing;
+pub mod app_egui;
+pub mod data_io;
+pub mod definitions;
+pub mod fetching;
+pub mod models;
+pub mod output_writers;
+pub mod reports;
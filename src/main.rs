@@ -1,16 +1,68 @@
 #![windows_subsystem = "windows"]
 
-mod analyzing;
-mod app_egui;
-mod data_io;
-mod definitions;
-mod models;
-
+use anyhow::anyhow;
 use std::env::args;
+use std::path::PathBuf;
+
+use jisrot::definitions::common::{CLI_ANALYZE_COMMAND, CLI_FETCH_COMMAND, CLI_SUMMARY_COMMAND};
+use jisrot::definitions::strings::message;
+use jisrot::{analyzing, app_egui, fetching};
+
+/// The GUI subsystem starts without a console, so CLI commands write to the console of the calling shell.
+/// Nothing is attached when started outside a console, e.g. by a scheduler, output is dropped then.
+#[cfg(windows)]
+fn attach_parent_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
 
 fn main() -> anyhow::Result<()> {
     let args: Vec<String> = args().collect();
 
+    if matches!(
+        args.get(1).map(String::as_str),
+        Some(CLI_SUMMARY_COMMAND | CLI_ANALYZE_COMMAND | CLI_FETCH_COMMAND)
+    ) {
+        attach_parent_console();
+    }
+
+    //  Print a Markdown summary of total stats files to stdout, e.g. for posting to chat
+    if args.get(1).map(String::as_str) == Some(CLI_SUMMARY_COMMAND) {
+        let total_stats_file_list: Vec<PathBuf> = args[2..].iter().map(PathBuf::from).collect();
+        if !(1..=2).contains(&total_stats_file_list.len()) {
+            return Err(anyhow!(message::error::CLI_SUMMARY_USAGE));
+        }
+
+        println!(
+            "{}",
+            analyzing::summarize_from_files(&total_stats_file_list)?
+        );
+        return Ok(());
+    }
+
+    //  Analyze event files to the chosen output formats, e.g. in scheduled jobs
+    if args.get(1).map(String::as_str) == Some(CLI_ANALYZE_COMMAND) {
+        println!("{}", analyzing::analyze_from_cli(&args[2..])?);
+        return Ok(());
+    }
+
+    //  Pull app events from Partner API to a local file for analyzing
+    if args.get(1).map(String::as_str) == Some(CLI_FETCH_COMMAND) {
+        println!("{}", fetching::fetch_from_cli(&args[2..])?);
+        return Ok(());
+    }
+
     if args[0] == "reset" {
         app_egui::run(true).expect("Cannot run egui app!");
     }
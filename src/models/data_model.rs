@@ -1,9 +1,17 @@
 use crate::definitions::common::*;
+use crate::definitions::strings::message::error::AMBIGUOUS_DATE_FORMAT as AMBIGUOUS_DATE_FORMAT_MESSAGE;
+use crate::definitions::strings::message::error::INVALID_DIMENSION as INVALID_DIMENSION_MESSAGE;
+use crate::definitions::strings::message::error::INVALID_EVENT_PATTERN as INVALID_EVENT_PATTERN_MESSAGE;
+use crate::definitions::strings::message::error::NO_DATE_FORMAT_MATCHED as NO_DATE_FORMAT_MATCHED_MESSAGE;
 use anyhow::{Result, anyhow};
-use chrono::{NaiveDate, NaiveDateTime};
-use getset::{Getters, MutGetters, Setters};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime};
+use getset::{CopyGetters, Getters, MutGetters, Setters};
 use indexmap::IndexMap;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BillingCycle {
@@ -13,6 +21,7 @@ pub enum BillingCycle {
 
 #[derive(Debug, Clone, Getters, Setters, Serialize, Deserialize)]
 #[getset(get = "pub", set = "pub")]
+#[serde(default)]
 pub struct Merchant {
     shop_domain: String,
     checked: bool,
@@ -24,18 +33,28 @@ pub struct Merchant {
     #[getset(get = "pub", set = "")]
     installing_events: Vec<AppEvent>,
 
+    /// Store closures are a lifecycle apart from app installs
+    #[getset(get = "pub", set = "")]
+    store_events: Vec<AppEvent>,
+    store_status: String,
+    store_closed_at_start: bool,
+    store_closed_at_end: bool,
+    days_closed: f64,
+
     subscription_activated_count: u32,
     subscription_canceled_count: u32,
+    subscription_frozen_count: u32,
+    subscription_unfrozen_count: u32,
 
     #[getset(get = "pub", set = "")]
     subscription_events: Vec<AppEvent>,
 
     one_time_count: u32,
 
-    #[getset(skip)]
+    #[getset(get = "pub", set = "")]
     one_time_details: IndexMap<String, u32>,
 
-    #[getset(skip)]
+    #[getset(get = "pub", set = "")]
     one_time_events: Vec<AppEvent>,
 
     installed_status: String,
@@ -44,6 +63,34 @@ pub struct Merchant {
     last_new_sub_billing_cycle: Option<BillingCycle>,
     first_canceled_sub_plan: Option<PricingUnit>,
     first_canceled_sub_billing_cycle: Option<BillingCycle>,
+
+    trial_status: String,
+    trial_plan: Option<PricingUnit>,
+    trial_started_time: Option<NaiveDateTime>,
+    trial_converted_time: Option<NaiveDateTime>,
+
+    subscription_revenue: f64,
+    one_time_revenue: f64,
+    lifetime_revenue: f64,
+    tenure_days: f64,
+
+    #[getset(get = "pub", set = "")]
+    reactivations: Vec<Reactivation>,
+
+    hours_to_first_subscription: Option<f64>,
+    hours_to_first_cancel: Option<f64>,
+    hours_to_uninstall: Option<f64>,
+
+    /// Installed before the period started
+    active_at_start: bool,
+    sub_plan_at_start: Option<PricingUnit>,
+    sub_billing_cycle_at_start: Option<BillingCycle>,
+    active_at_end: bool,
+    sub_plan_at_end: Option<PricingUnit>,
+    sub_billing_cycle_at_end: Option<BillingCycle>,
+
+    /// Start state is taken from the opening state instead of guessed from events
+    carried_over: bool,
 }
 
 impl Merchant {
@@ -62,8 +109,15 @@ impl Merchant {
             store_closed_count: 0,
             store_reopened_count: 0,
             installing_events: Vec::new(),
+            store_events: Vec::new(),
+            store_status: STORE_STATUS_OPEN.to_string(),
+            store_closed_at_start: false,
+            store_closed_at_end: false,
+            days_closed: 0.0,
             subscription_activated_count: 0,
             subscription_canceled_count: 0,
+            subscription_frozen_count: 0,
+            subscription_unfrozen_count: 0,
             subscription_events: Vec::new(),
             one_time_count: 0,
             one_time_details: one_time_details,
@@ -74,9 +128,72 @@ impl Merchant {
             last_new_sub_billing_cycle: None,
             first_canceled_sub_plan: None,
             first_canceled_sub_billing_cycle: None,
+            trial_status: NONE.to_string(),
+            trial_plan: None,
+            trial_started_time: None,
+            trial_converted_time: None,
+            subscription_revenue: 0.0,
+            one_time_revenue: 0.0,
+            lifetime_revenue: 0.0,
+            tenure_days: 0.0,
+            reactivations: Vec::new(),
+            hours_to_first_subscription: None,
+            hours_to_first_cancel: None,
+            hours_to_uninstall: None,
+            active_at_start: false,
+            sub_plan_at_start: None,
+            sub_billing_cycle_at_start: None,
+            active_at_end: false,
+            sub_plan_at_end: None,
+            sub_billing_cycle_at_end: None,
+            carried_over: false,
         }
     }
 
+    /// New merchant for the next period, starting from the end state of this merchant.
+    /// Merchant data written before the end state was tracked falls back to the final statuses.
+    pub fn carry_over(&self, one_time_packs: &Vec<PricingUnit>) -> Self {
+        let mut merchant = Self::new(&self.shop_domain, one_time_packs);
+
+        let active = self.ends_active();
+        let (sub_plan, sub_billing_cycle) = if !active {
+            (None, None)
+        } else if self.sub_plan_at_end.is_some() {
+            (
+                self.sub_plan_at_end.clone(),
+                self.sub_billing_cycle_at_end.clone(),
+            )
+        } else if self.subscription_status == SUBSCRIPTION_STATUS_ACTIVE {
+            (
+                self.last_new_sub_plan.clone(),
+                self.last_new_sub_billing_cycle.clone(),
+            )
+        } else {
+            (None, None)
+        };
+
+        merchant.active_at_start = active;
+        merchant.store_closed_at_start = active && self.store_closed_at_end;
+        merchant.sub_plan_at_start = sub_plan;
+        merchant.sub_billing_cycle_at_start = sub_billing_cycle;
+        merchant.carried_over = true;
+        merchant
+    }
+
+    /// Whether this merchant is still installed at the end of its period
+    pub fn ends_active(&self) -> bool {
+        self.active_at_end || self.installed_status == INSTALLED_STRING
+    }
+
+    /// All events kept for this merchant
+    pub fn events(&self) -> impl Iterator<Item = &AppEvent> {
+        self.installing_events
+            .iter()
+            .chain(self.store_events.iter())
+            .chain(self.subscription_events.iter())
+            .chain(self.one_time_events.iter())
+    }
+
     pub fn increase_one_time_count(&mut self, count: u32) {
         self.one_time_count += count;
     }
@@ -121,6 +238,14 @@ impl Merchant {
         self.subscription_activated_count += count;
     }
 
+    pub fn increase_subscription_frozen_count(&mut self, count: u32) {
+        self.subscription_frozen_count += count;
+    }
+
+    pub fn increase_subscription_unfrozen_count(&mut self, count: u32) {
+        self.subscription_unfrozen_count += count;
+    }
+
     pub fn push_subscription_event(&mut self, event: &AppEvent) {
         self.subscription_events.push(event.clone());
     }
@@ -132,6 +257,67 @@ impl Merchant {
     pub fn push_installing_event(&mut self, event: &AppEvent) {
         self.installing_events.push(event.clone());
     }
+
+    pub fn push_store_event(&mut self, event: &AppEvent) {
+        self.store_events.push(event.clone());
+    }
+
+    pub fn push_reactivation(&mut self, reactivation: Reactivation) {
+        self.reactivations.push(reactivation);
+    }
+
+    pub fn last_reactivation_mut(&mut self) -> Option<&mut Reactivation> {
+        self.reactivations.last_mut()
+    }
+}
+
+impl Default for Merchant {
+    fn default() -> Self {
+        Self::new(&String::new(), &Vec::new())
+    }
+}
+
+/// A merchant coming back: reinstalling after uninstall, or re-subscribing after cancel
+#[derive(Debug, Clone, Getters, Setters, Serialize, Deserialize)]
+#[getset(get = "pub", set = "pub")]
+pub struct Reactivation {
+    kind: String,
+    lost_time: NaiveDateTime,
+    returned_time: NaiveDateTime,
+    gap_days: f64,
+
+    /// Plan code before leaving
+    previous_plan: Option<String>,
+
+    /// Plan code after coming back, for reinstall it is the first plan activated after reinstalling
+    returned_plan: Option<String>,
+}
+
+impl Reactivation {
+    pub fn new(
+        kind: &str,
+        lost_time: NaiveDateTime,
+        returned_time: NaiveDateTime,
+        previous_plan: Option<String>,
+        returned_plan: Option<String>,
+    ) -> Self {
+        Self {
+            kind: kind.to_string(),
+            lost_time,
+            returned_time,
+            gap_days: (returned_time - lost_time).num_seconds() as f64 / 86400.0,
+            previous_plan,
+            returned_plan,
+        }
+    }
+
+    /// None if either plan is unknown
+    pub fn is_same_plan(&self) -> Option<bool> {
+        match (&self.previous_plan, &self.returned_plan) {
+            (Some(previous), Some(returned)) => Some(previous == returned),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Getters, MutGetters, Setters, Serialize, Deserialize)]
@@ -157,6 +343,26 @@ impl MerchantList {
         self.merchants
             .insert(merchant.shop_domain().clone(), merchant);
     }
+
+    /// Copy of this list with only the given merchants
+    pub fn subset<'a>(&self, shop_domains: impl Iterator<Item = &'a String>) -> Self {
+        let mut merchant_list = Self::new();
+        merchant_list.start_time = self.start_time;
+        merchant_list.end_time = self.end_time;
+
+        for shop_domain in shop_domains {
+            if let Some(merchant) = self.merchants.get(shop_domain) {
+                merchant_list.update_merchant(merchant.clone());
+            }
+        }
+        merchant_list
+    }
+}
+
+impl Default for MerchantList {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[derive(Debug, Clone, Getters, MutGetters, Setters, Serialize, Deserialize)]
@@ -177,26 +383,88 @@ pub struct TotalStats {
     store_closed_count: u32,
     store_reopened_count: u32,
 
+    /// Reopened minus closed stores, not counted in merchant growth
+    #[serde(default)]
+    store_closure_growth: i32,
+    /// Merchants with the store closed at period end
+    #[serde(default)]
+    closed_store_count: u32,
+    /// Average days closed in the period of merchants closed at any time
+    #[serde(default)]
+    average_days_closed: f64,
+
     one_time_count: u32,
 
     #[getset(skip)]
     one_time_details: IndexMap<String, u32>,
 
+    #[serde(default)]
+    one_time_summary: OneTimePackStats,
+    #[serde(default)]
+    one_time_pack_stats: IndexMap<String, OneTimePackStats>,
+
     new_sub_count: u32,
     canceled_sub_count: u32,
     sub_growth: i32,
 
+    #[serde(default)]
+    new_paying_sub_count: u32,
+    #[serde(default)]
+    canceled_paying_sub_count: u32,
+    #[serde(default)]
+    paying_sub_growth: i32,
+
+    #[serde(default)]
+    new_free_sub_count: u32,
+    #[serde(default)]
+    canceled_free_sub_count: u32,
+    #[serde(default)]
+    free_sub_growth: i32,
+
+    /// Frozen and unfrozen charges are not counted in new and canceled subscriptions
+    #[serde(default)]
+    frozen_sub_count: u32,
+    #[serde(default)]
+    unfrozen_sub_count: u32,
+    #[serde(default)]
+    frozen_then_canceled_sub_count: u32,
+
     sub_stats_details: DetailedSubscriptionStats,
+    #[serde(default)]
+    paying_sub_stats_details: DetailedSubscriptionStats,
+    #[serde(default)]
+    free_sub_stats_details: DetailedSubscriptionStats,
 
     paid_growth: i32,
+
+    #[serde(default)]
+    trial_stats: TrialStats,
+
+    #[serde(default)]
+    revenue_stats: RevenueStats,
+
+    #[serde(default)]
+    reactivation_stats: ReactivationStats,
+
+    #[serde(default)]
+    time_to_event_stats: TimeToEventStats,
+
+    #[serde(default)]
+    churn_stats: ChurnStats,
+    #[serde(default)]
+    headline_churn_formula: ChurnFormula,
+    #[serde(default)]
+    headline_churn_rate: f64,
 }
 
 impl TotalStats {
     pub fn new(pricing_defs: &PricingDefs) -> Self {
         let mut one_time_details: IndexMap<String, u32> = IndexMap::new();
+        let mut one_time_pack_stats: IndexMap<String, OneTimePackStats> = IndexMap::new();
 
         for pack in pricing_defs.one_times.iter() {
             one_time_details.insert(pack.code.clone(), 0);
+            one_time_pack_stats.insert(pack.code.clone(), OneTimePackStats::new());
         }
 
         Self {
@@ -212,25 +480,63 @@ impl TotalStats {
             merchant_growth: 0,
             store_closed_count: 0,
             store_reopened_count: 0,
+            store_closure_growth: 0,
+            closed_store_count: 0,
+            average_days_closed: 0.0,
             one_time_count: 0,
             one_time_details: one_time_details,
+            one_time_summary: OneTimePackStats::new(),
+            one_time_pack_stats,
             new_sub_count: 0,
             canceled_sub_count: 0,
             sub_growth: 0,
+            new_paying_sub_count: 0,
+            canceled_paying_sub_count: 0,
+            paying_sub_growth: 0,
+            new_free_sub_count: 0,
+            canceled_free_sub_count: 0,
+            free_sub_growth: 0,
+            frozen_sub_count: 0,
+            unfrozen_sub_count: 0,
+            frozen_then_canceled_sub_count: 0,
             sub_stats_details: DetailedSubscriptionStats::new(&pricing_defs.subscriptions),
+            paying_sub_stats_details: DetailedSubscriptionStats::new(
+                &pricing_defs.paying_subscriptions(),
+            ),
+            free_sub_stats_details: DetailedSubscriptionStats::new(
+                &pricing_defs.free_subscriptions(),
+            ),
             paid_growth: 0,
+            trial_stats: TrialStats::new(),
+            revenue_stats: RevenueStats::new(),
+            reactivation_stats: ReactivationStats::new(),
+            time_to_event_stats: TimeToEventStats::new(),
+            churn_stats: ChurnStats::new(&pricing_defs.subscriptions),
+            headline_churn_formula: ChurnFormula::default(),
+            headline_churn_rate: 0.0,
         }
     }
 
     pub fn build_pretty_time_str(&mut self) {
         if let Some(t) = self.start_time {
-            self.start_time_str = t.format("%b%d").to_string();
+            self.start_time_str = t.format(SHORT_DATE_PATTERN).to_string();
         }
         if let Some(t) = self.end_time {
-            self.end_time_str = t.format("%b%d").to_string();
+            self.end_time_str = t.format(SHORT_DATE_PATTERN).to_string();
         }
     }
 
+    /// Pick the churn rate shown as headline from the given formula
+    pub fn select_headline_churn(&mut self, formula: &ChurnFormula) {
+        self.headline_churn_formula = formula.clone();
+        self.headline_churn_rate = match formula {
+            ChurnFormula::InstallBased => self.total_churn_rate,
+            ChurnFormula::NetInstallBased => self.churn_rate,
+            ChurnFormula::Logo => self.churn_stats.logo_churn_rate,
+            ChurnFormula::Revenue => self.churn_stats.revenue_churn_rate,
+        };
+    }
+
     pub fn increase_one_time_count(&mut self, count: u32) {
         self.one_time_count += count;
     }
@@ -271,6 +577,10 @@ impl TotalStats {
         self.store_reopened_count += count;
     }
 
+    pub fn increase_closed_store_count(&mut self, count: u32) {
+        self.closed_store_count += count;
+    }
+
     pub fn increase_new_sub_count(&mut self, count: u32) {
         self.new_sub_count += count;
     }
@@ -278,197 +588,1630 @@ impl TotalStats {
     pub fn increase_canceled_sub_count(&mut self, count: u32) {
         self.canceled_sub_count += count;
     }
+
+    pub fn increase_new_paying_sub_count(&mut self, count: u32) {
+        self.new_paying_sub_count += count;
+    }
+
+    pub fn increase_canceled_paying_sub_count(&mut self, count: u32) {
+        self.canceled_paying_sub_count += count;
+    }
+
+    pub fn increase_new_free_sub_count(&mut self, count: u32) {
+        self.new_free_sub_count += count;
+    }
+
+    pub fn increase_canceled_free_sub_count(&mut self, count: u32) {
+        self.canceled_free_sub_count += count;
+    }
+
+    pub fn increase_frozen_sub_count(&mut self, count: u32) {
+        self.frozen_sub_count += count;
+    }
+
+    pub fn increase_unfrozen_sub_count(&mut self, count: u32) {
+        self.unfrozen_sub_count += count;
+    }
+
+    pub fn increase_frozen_then_canceled_sub_count(&mut self, count: u32) {
+        self.frozen_then_canceled_sub_count += count;
+    }
+
+    /// Paying or free subscription details, depending on the given plan
+    pub fn split_sub_stats_details_mut(
+        &mut self,
+        plan: &PricingUnit,
+    ) -> &mut DetailedSubscriptionStats {
+        if plan.is_paying() {
+            &mut self.paying_sub_stats_details
+        } else {
+            &mut self.free_sub_stats_details
+        }
+    }
 }
 
-#[derive(Debug, Deserialize, Getters, Setters)]
+#[derive(Debug, Clone, Getters, Setters, Serialize, Deserialize)]
 #[getset(get = "pub", set = "pub")]
-pub struct ExcludingDef {
-    excluding_field: String,
-    excluding_pattern: String,
+pub struct TrialStats {
+    started_count: u32,
+    converted_count: u32,
+    expired_count: u32,
+    uninstalled_count: u32,
+    ongoing_count: u32,
+
+    /// Converted trials over trials that already ended (converted, expired or uninstalled)
+    conversion_rate: f64,
+    median_days_to_conversion: f64,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Getters, Setters)]
-#[getset(get = "pub", set = "pub")]
-pub struct PricingUnit {
-    code: String,
-    name: String,
-    regex_pattern: String,
-    price: f64,
-    currency: String,
+impl TrialStats {
+    pub fn new() -> Self {
+        Self {
+            started_count: 0,
+            converted_count: 0,
+            expired_count: 0,
+            uninstalled_count: 0,
+            ongoing_count: 0,
+            conversion_rate: 0.0,
+            median_days_to_conversion: 0.0,
+        }
+    }
+
+    pub fn increase_started_count(&mut self, count: u32) {
+        self.started_count += count;
+    }
+
+    pub fn increase_converted_count(&mut self, count: u32) {
+        self.converted_count += count;
+    }
+
+    pub fn increase_expired_count(&mut self, count: u32) {
+        self.expired_count += count;
+    }
+
+    pub fn increase_uninstalled_count(&mut self, count: u32) {
+        self.uninstalled_count += count;
+    }
+
+    pub fn increase_ongoing_count(&mut self, count: u32) {
+        self.ongoing_count += count;
+    }
 }
 
-#[derive(Debug, Deserialize, Getters, Setters)]
-#[getset(get = "pub", set = "pub")]
-pub struct PricingDefs {
-    subscriptions: Vec<PricingUnit>,
-    one_times: Vec<PricingUnit>,
+impl Default for TrialStats {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-#[derive(Debug, Setters, Getters, MutGetters, Serialize, Deserialize, Clone)]
-#[getset(get = "pub", get_mut = "pub", set = "pub")]
-pub struct SubscriptionStatsCounter {
-    monthly_counts: IndexMap<String, i32>,
-    yearly_counts: IndexMap<String, i32>,
+/// Formula of the churn rate shown as headline
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum ChurnFormula {
+    /// Uninstalls / installs in the period
+    #[default]
+    InstallBased,
+    /// (Uninstalls - uninstalls of merchants installed before the period) / installs in the period
+    NetInstallBased,
+    /// Merchants lost / merchants active at period start
+    Logo,
+    /// MRR lost / MRR at period start
+    Revenue,
 }
 
-impl SubscriptionStatsCounter {
-    pub fn new(subscription_plan_list: &Vec<PricingUnit>) -> Self {
-        let mut monthly_counts: IndexMap<String, i32> = IndexMap::new();
-        let mut yearly_counts: IndexMap<String, i32> = IndexMap::new();
+impl ChurnFormula {
+    pub const ALL: [ChurnFormula; 4] = [
+        ChurnFormula::InstallBased,
+        ChurnFormula::NetInstallBased,
+        ChurnFormula::Logo,
+        ChurnFormula::Revenue,
+    ];
+}
 
-        for subscription_plan in subscription_plan_list.iter() {
-            monthly_counts.insert(subscription_plan.code.clone(), 0);
-            yearly_counts.insert(subscription_plan.code.clone(), 0);
+impl fmt::Display for ChurnFormula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InstallBased => write!(f, "{}", CHURN_FORMULA_INSTALL_BASED),
+            Self::NetInstallBased => write!(f, "{}", CHURN_FORMULA_NET_INSTALL_BASED),
+            Self::Logo => write!(f, "{}", CHURN_FORMULA_LOGO),
+            Self::Revenue => write!(f, "{}", CHURN_FORMULA_REVENUE),
         }
+    }
+}
 
+/// Definitions and options applied to every analysis of a run, including each breakdown group
+#[derive(Debug, Clone, Copy, CopyGetters)]
+#[getset(get_copy = "pub")]
+pub struct AnalysisSettings<'a> {
+    pricing_defs: &'a PricingDefs,
+    excluding_defs: &'a ExcludingDef,
+    case_sensitive_regex: bool,
+    churn_formula: &'a ChurnFormula,
+}
+
+impl<'a> AnalysisSettings<'a> {
+    pub fn new(
+        pricing_defs: &'a PricingDefs,
+        excluding_defs: &'a ExcludingDef,
+        case_sensitive_regex: bool,
+        churn_formula: &'a ChurnFormula,
+    ) -> Self {
         Self {
-            monthly_counts: monthly_counts,
-            yearly_counts: yearly_counts,
+            pricing_defs,
+            excluding_defs,
+            case_sensitive_regex,
+            churn_formula,
         }
     }
+}
 
-    pub fn increase(
-        &mut self,
-        subscription_plan: &PricingUnit,
-        billing_cycle: &BillingCycle,
-        count: i32,
-    ) -> anyhow::Result<()> {
-        match billing_cycle {
-            BillingCycle::Monthly => {
-                if let Some(entry) = self.monthly_counts.get_mut(&subscription_plan.code) {
-                    *entry += count;
-                    Ok(())
-                } else {
-                    Err(anyhow!(
-                        "[SubscriptionStatsCounter] Subscription plan code {} not found in initialized monthly count stats",
-                        subscription_plan.code
-                    ))
-                }
-            }
+/// What to do when an output file already exists
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum OverwritePolicy {
+    #[default]
+    Overwrite,
+    Skip,
+    /// Add a number to the file name, e.g. total_stats_2.json
+    Suffix,
+}
 
-            BillingCycle::Yearly => {
-                if let Some(entry) = self.yearly_counts.get_mut(&subscription_plan.code) {
-                    *entry += count;
-                    Ok(())
-                } else {
-                    Err(anyhow!(
-                        "[SubscriptionStatsCounter] Subscription plan code {} not found in initialized yearly count stats",
-                        subscription_plan.code
-                    ))
-                }
-            }
+impl OverwritePolicy {
+    pub const ALL: [OverwritePolicy; 3] = [
+        OverwritePolicy::Overwrite,
+        OverwritePolicy::Skip,
+        OverwritePolicy::Suffix,
+    ];
+}
+
+impl fmt::Display for OverwritePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Overwrite => write!(f, "{}", OVERWRITE_POLICY_OVERWRITE),
+            Self::Skip => write!(f, "{}", OVERWRITE_POLICY_SKIP),
+            Self::Suffix => write!(f, "{}", OVERWRITE_POLICY_SUFFIX),
         }
     }
 }
 
-#[derive(Debug, Getters, MutGetters, Setters, Serialize, Deserialize, Clone)]
-#[getset(get = "pub", get_mut = "pub", set = "pub")]
-pub struct DetailedSubscriptionStats {
-    new_sub: SubscriptionStatsCounter,
-    canceled_sub: SubscriptionStatsCounter,
-    sub_growth: SubscriptionStatsCounter,
-    all_new_sub: SubscriptionStatsCounter,
-    all_canceled_sub: SubscriptionStatsCounter,
+/// Partner API query of app events of an app in a date range
+#[derive(Debug, Clone, Getters, Setters)]
+#[getset(get = "pub", set = "pub")]
+pub struct PartnerApiFetch {
+    /// GraphQL URL of the organization, or of a mock server
+    endpoint: String,
+    access_token: String,
+
+    /// App GID, e.g. gid://partners/App/1234
+    app_id: String,
+    occurred_at_min: Option<NaiveDate>,
+    occurred_at_max: Option<NaiveDate>,
+    page_size: u32,
+    out_file: PathBuf,
 }
 
-impl DetailedSubscriptionStats {
-    pub fn new(subscription_plan_list: &Vec<PricingUnit>) -> Self {
+impl PartnerApiFetch {
+    pub fn new(
+        endpoint: &str,
+        access_token: &str,
+        app_id: &str,
+        occurred_at_min: Option<NaiveDate>,
+        occurred_at_max: Option<NaiveDate>,
+        page_size: u32,
+        out_file: &Path,
+    ) -> Self {
+        let app_id: &str = app_id.trim();
         Self {
-            new_sub: SubscriptionStatsCounter::new(subscription_plan_list),
-            canceled_sub: SubscriptionStatsCounter::new(subscription_plan_list),
-            sub_growth: SubscriptionStatsCounter::new(subscription_plan_list),
-            all_new_sub: SubscriptionStatsCounter::new(subscription_plan_list),
-            all_canceled_sub: SubscriptionStatsCounter::new(subscription_plan_list),
+            endpoint: endpoint.to_string(),
+            access_token: access_token.to_string(),
+            //  A bare numeric app ID, as shown in Partner Dashboard URLs, is turned into its GID
+            app_id: if !app_id.is_empty() && app_id.chars().all(|c| c.is_ascii_digit()) {
+                PARTNER_API_APP_GID_PREFIX.to_string() + app_id
+            } else {
+                app_id.to_string()
+            },
+            occurred_at_min,
+            occurred_at_max,
+            page_size,
+            out_file: out_file.to_path_buf(),
         }
     }
+
+    /// Checkpoint file saved next to the out file
+    pub fn checkpoint_file(&self) -> PathBuf {
+        let mut file_name = self.out_file.file_name().unwrap_or_default().to_os_string();
+        file_name.push(PARTNER_API_CHECKPOINT_SUFFIX);
+        self.out_file.with_file_name(file_name)
+    }
 }
 
-#[derive(Debug, Clone, Getters, Setters, Serialize, Deserialize)]
+/// Progress of a fetch saved after each page, to resume an interrupted fetch
+#[derive(Debug, Clone, Default, Getters, Setters, Serialize, Deserialize, PartialEq)]
 #[getset(get = "pub", set = "pub")]
-pub struct AppEvent {
-    time: Option<NaiveDateTime>,
-    event: String,
-    details: String,
-    billing_on: Option<NaiveDateTime>,
-    shop_name: String,
-    shop_country: String,
-    shop_email: String,
-    shop_domain: String,
-    excluding_check_data: String,
+#[serde(default)]
+pub struct FetchCheckpoint {
+    app_id: String,
+    occurred_at_min: Option<NaiveDate>,
+    occurred_at_max: Option<NaiveDate>,
+
+    /// Cursor of the last stored event, None before the first page
+    cursor: Option<String>,
+    page_count: u32,
+    event_count: u32,
 }
 
-impl AppEvent {
-    pub fn _new() -> Self {
-        AppEvent {
-            time: None,
-            event: String::default(),
-            details: String::default(),
-            billing_on: None,
-            shop_name: String::default(),
-            shop_country: String::default(),
-            shop_email: String::default(),
-            shop_domain: String::default(),
-            excluding_check_data: String::default(),
+impl FetchCheckpoint {
+    pub fn new(fetch: &PartnerApiFetch) -> Self {
+        Self {
+            app_id: fetch.app_id().clone(),
+            occurred_at_min: *fetch.occurred_at_min(),
+            occurred_at_max: *fetch.occurred_at_max(),
+            ..Default::default()
         }
     }
 
-    fn parse_time(
-        data_hash: &IndexMap<String, String>,
-        data_field: &str,
-        pattern: &str,
-    ) -> Result<NaiveDateTime, String> {
-        if let Some(time_string) = data_hash.get(data_field) {
-            match NaiveDateTime::parse_from_str(time_string.as_str(), pattern) {
-                Ok(date_time) => Ok(date_time),
-
-                Err(e) => {
-                    eprintln!("Date Time Parse error: {:?}\n Trying parse Date only...", e);
-                    match NaiveDate::parse_from_str(time_string.as_str(), pattern) {
-                        Ok(date) => {
-                            if let Some(date_time_fk) = date.and_hms_opt(0, 0, 0) {
-                                Ok(date_time_fk)
-                            } else {
-                                Err(format!(
-                                    "Invalid date format for field {}: {}",
-                                    data_field, time_string
-                                ))
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("Date Parse error: {:?}", e);
-                            Err(format!(
-                                "Invalid date format for field {}: {}",
-                                data_field, time_string
-                            ))
-                        }
-                    }
-                }
+    /// Whether the checkpoint is of the same query
+    pub fn matches(&self, fetch: &PartnerApiFetch) -> bool {
+        self.app_id == *fetch.app_id()
+            && self.occurred_at_min == *fetch.occurred_at_min()
+            && self.occurred_at_max == *fetch.occurred_at_max()
+    }
+}
+
+/// Output folder and file naming scheme of a run
+#[derive(Debug, Clone, Getters, Setters)]
+#[getset(get = "pub", set = "pub")]
+pub struct OutputNaming {
+    out_folder: PathBuf,
+
+    /// File name without extension, with `NAME_PLACEHOLDER_*` placeholders
+    name_template: String,
+
+    /// App key or pricing definitions preset name
+    app_name: String,
+    run_time: NaiveDateTime,
+    overwrite_policy: OverwritePolicy,
+}
+
+impl OutputNaming {
+    pub fn new(
+        out_folder: &Path,
+        name_template: &str,
+        app_name: &str,
+        overwrite_policy: &OverwritePolicy,
+    ) -> Self {
+        Self {
+            out_folder: out_folder.to_path_buf(),
+            name_template: if name_template.trim().is_empty() {
+                DEFAULT_OUT_FILE_NAME_TEMPLATE.to_string()
+            } else {
+                name_template.trim().to_string()
+            },
+            app_name: app_name.to_string(),
+            run_time: chrono::Local::now().naive_local(),
+            overwrite_policy: overwrite_policy.clone(),
+        }
+    }
+
+    /// Output file of a data kind over a period, numbered if it exists and the policy is to add suffix.
+    /// Empty placeholders are dropped with their separator
+    pub fn out_file(
+        &self,
+        data_pref: &str,
+        start_time: &Option<NaiveDateTime>,
+        end_time: &Option<NaiveDateTime>,
+        extension: &str,
+    ) -> PathBuf {
+        let format_time = |time: &Option<NaiveDateTime>, pattern: &str| {
+            time.map(|t| t.format(pattern).to_string())
+                .unwrap_or_default()
+        };
+
+        let name: String = self
+            .name_template
+            .replace(NAME_PLACEHOLDER_DATA, data_pref)
+            .replace(NAME_PLACEHOLDER_APP, &self.app_name)
+            .replace(
+                NAME_PLACEHOLDER_START_DATE,
+                &format_time(start_time, HISTORY_DATE_PATTERN),
+            )
+            .replace(
+                NAME_PLACEHOLDER_END_DATE,
+                &format_time(end_time, HISTORY_DATE_PATTERN),
+            )
+            .replace(
+                NAME_PLACEHOLDER_START,
+                &format_time(start_time, SHORT_DATE_PATTERN),
+            )
+            .replace(
+                NAME_PLACEHOLDER_END,
+                &format_time(end_time, SHORT_DATE_PATTERN),
+            )
+            .replace(
+                NAME_PLACEHOLDER_RUN,
+                &self.run_time.format(RUN_TIME_PATTERN).to_string(),
+            )
+            .replace(['/', '\\'], "-")
+            .split(NAME_SEPARATOR)
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<&str>>()
+            .join(&NAME_SEPARATOR.to_string());
+
+        let file_name = |number: Option<u32>| {
+            let numbered_name = match number {
+                Some(n) => format!("{}{}{}", name, NAME_SEPARATOR, n),
+                None => name.clone(),
+            };
+            if extension.is_empty() {
+                numbered_name
+            } else {
+                format!("{}.{}", numbered_name, extension)
+            }
+        };
+
+        let mut out_file: PathBuf = self.out_folder.join(file_name(None));
+        if self.overwrite_policy == OverwritePolicy::Suffix {
+            let mut number: u32 = 2;
+            while out_file.exists() {
+                out_file = self.out_folder.join(file_name(Some(number)));
+                number += 1;
             }
-        } else {
-            return Err(format!("Missing required field: {}", data_field));
         }
+        out_file
     }
 
-    pub fn from_indexmap(
-        source: &IndexMap<String, String>,
-        excluding_check_field: &str,
-    ) -> Result<Self, String> {
-        let mut time = None;
-        let mut billing_on = None;
+    /// The file exists and the policy is to skip it
+    pub fn skips(&self, out_file: &Path) -> bool {
+        self.overwrite_policy == OverwritePolicy::Skip && out_file.exists()
+    }
+}
 
-        match Self::parse_time(source, TIME_FIELD, EVENT_TIME_PATTERN) {
-            Ok(date_time) => time = Some(date_time),
-            Err(e) => println!("Warning: {}", e),
-        }
+/// Outputs chosen for an analysis run, written for each analyzed file or period
+#[derive(Debug, Clone, Copy, CopyGetters)]
+#[getset(get_copy = "pub")]
+pub struct OutputOptions<'a> {
+    output_naming: &'a OutputNaming,
+    output_formats: &'a [String],
 
-        match Self::parse_time(source, BILLING_ON_FIELD, BILLING_ON_PATTERN) {
-            Ok(date_time) => billing_on = Some(date_time),
-            Err(e) => println!("Warning: {}", e),
+    /// Template name and source
+    report_template: Option<(&'a str, &'a str)>,
+    breakdown_dimensions: &'a [GroupingDimension],
+
+    /// Merchant data and raw events are written too
+    debug_mode: bool,
+}
+
+impl<'a> OutputOptions<'a> {
+    pub fn new(
+        output_naming: &'a OutputNaming,
+        output_formats: &'a [String],
+        report_template: Option<(&'a str, &'a str)>,
+        breakdown_dimensions: &'a [GroupingDimension],
+        debug_mode: bool,
+    ) -> Self {
+        Self {
+            output_naming,
+            output_formats,
+            report_template,
+            breakdown_dimensions,
+            debug_mode,
         }
+    }
+}
 
-        Ok(AppEvent {
-            time,
-            event: source.get(EVENT_FIELD).cloned().unwrap_or_default(),
+#[derive(Debug, Clone, Getters, Setters, Serialize, Deserialize)]
+#[getset(get = "pub", set = "pub")]
+pub struct PlanChurnStats {
+    starting_count: u32,
+    churned_count: u32,
+    churn_rate: f64,
+}
+
+impl PlanChurnStats {
+    pub fn new() -> Self {
+        Self {
+            starting_count: 0,
+            churned_count: 0,
+            churn_rate: 0.0,
+        }
+    }
+}
+
+impl Default for PlanChurnStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Churn measured against the active base at period start
+#[derive(Debug, Clone, Getters, MutGetters, Setters, Serialize, Deserialize)]
+#[getset(get = "pub", set = "pub")]
+pub struct ChurnStats {
+    starting_active_count: u32,
+    ending_active_count: u32,
+    starting_sub_count: u32,
+    ending_sub_count: u32,
+    churned_count: u32,
+    /// Churned merchants split by cause, an uninstall wins over a store closure
+    #[serde(default)]
+    churned_uninstalled_count: u32,
+    #[serde(default)]
+    churned_store_closed_count: u32,
+    logo_churn_rate: f64,
+
+    starting_mrr: f64,
+    churned_mrr: f64,
+    revenue_churn_rate: f64,
+
+    /// Subscriptions at period start which are no longer on the same plan at period end
+    #[getset(get = "pub", get_mut = "pub", set = "")]
+    sub_churn: IndexMap<String, PlanChurnStats>,
+}
+
+impl ChurnStats {
+    pub fn new(subscription_plan_list: &[PricingUnit]) -> Self {
+        let mut sub_churn: IndexMap<String, PlanChurnStats> = IndexMap::new();
+        for plan in subscription_plan_list.iter() {
+            sub_churn.insert(plan.code.clone(), PlanChurnStats::new());
+        }
+
+        Self {
+            starting_active_count: 0,
+            ending_active_count: 0,
+            starting_sub_count: 0,
+            ending_sub_count: 0,
+            churned_count: 0,
+            churned_uninstalled_count: 0,
+            churned_store_closed_count: 0,
+            logo_churn_rate: 0.0,
+            starting_mrr: 0.0,
+            churned_mrr: 0.0,
+            revenue_churn_rate: 0.0,
+            sub_churn,
+        }
+    }
+
+    pub fn add_merchant(&mut self, merchant: &Merchant) {
+        //  A closed store is installed but not active
+        let active_at_start = merchant.active_at_start && !merchant.store_closed_at_start;
+        let active_at_end = merchant.active_at_end && !merchant.store_closed_at_end;
+
+        if active_at_start {
+            self.starting_active_count += 1;
+            if !active_at_end {
+                self.churned_count += 1;
+                if merchant.active_at_end {
+                    self.churned_store_closed_count += 1;
+                } else {
+                    self.churned_uninstalled_count += 1;
+                }
+            }
+        }
+        if active_at_end {
+            self.ending_active_count += 1;
+        }
+        if merchant.sub_plan_at_start.is_some() {
+            self.starting_sub_count += 1;
+        }
+        if merchant.sub_plan_at_end.is_some() {
+            self.ending_sub_count += 1;
+        }
+
+        if let (Some(plan), Some(billing_cycle)) = (
+            &merchant.sub_plan_at_start,
+            &merchant.sub_billing_cycle_at_start,
+        ) {
+            let starting_mrr = plan.monthly_price(billing_cycle);
+            let ending_mrr = match (
+                &merchant.sub_plan_at_end,
+                &merchant.sub_billing_cycle_at_end,
+            ) {
+                (Some(end_plan), Some(end_billing_cycle)) => {
+                    end_plan.monthly_price(end_billing_cycle)
+                }
+                _ => 0.0,
+            };
+            self.starting_mrr += starting_mrr;
+            //  Downgrades count as partial revenue churn
+            self.churned_mrr += (starting_mrr - ending_mrr).max(0.0);
+
+            if let Some(plan_churn) = self.sub_churn.get_mut(&plan.code) {
+                plan_churn.starting_count += 1;
+                if merchant
+                    .sub_plan_at_end
+                    .as_ref()
+                    .is_none_or(|end_plan| end_plan.code != plan.code)
+                {
+                    plan_churn.churned_count += 1;
+                }
+            }
+        }
+    }
+
+    pub fn calculate_rates(&mut self) {
+        self.logo_churn_rate = if self.starting_active_count > 0 {
+            self.churned_count as f64 / self.starting_active_count as f64 * 100.0
+        } else {
+            0.0
+        };
+
+        self.revenue_churn_rate = if self.starting_mrr > 0.0 {
+            self.churned_mrr / self.starting_mrr * 100.0
+        } else {
+            0.0
+        };
+
+        for plan_churn in self.sub_churn.values_mut() {
+            plan_churn.churn_rate = if plan_churn.starting_count > 0 {
+                plan_churn.churned_count as f64 / plan_churn.starting_count as f64 * 100.0
+            } else {
+                0.0
+            };
+        }
+    }
+}
+
+impl Default for ChurnStats {
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+/// Distribution of durations in hours, as percentiles and histogram
+#[derive(Debug, Clone, Getters, Setters, Serialize, Deserialize)]
+#[getset(get = "pub", set = "pub")]
+pub struct DurationDistribution {
+    count: u32,
+    p25_hours: f64,
+    p50_hours: f64,
+    p75_hours: f64,
+    p90_hours: f64,
+
+    histogram: IndexMap<String, u32>,
+
+    #[getset(skip)]
+    #[serde(skip)]
+    hours_list: Vec<f64>,
+}
+
+impl DurationDistribution {
+    pub fn new() -> Self {
+        let mut histogram: IndexMap<String, u32> = IndexMap::new();
+        for (label, _) in DURATION_BUCKETS.iter() {
+            histogram.insert(label.to_string(), 0);
+        }
+
+        Self {
+            count: 0,
+            p25_hours: 0.0,
+            p50_hours: 0.0,
+            p75_hours: 0.0,
+            p90_hours: 0.0,
+            histogram,
+            hours_list: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, hours: f64) {
+        self.count += 1;
+        self.hours_list.push(hours);
+
+        if let Some((label, _)) = DURATION_BUCKETS.iter().find(|(_, max)| hours < *max)
+            && let Some(entry) = self.histogram.get_mut(*label)
+        {
+            *entry += 1;
+        }
+    }
+
+    /// Percentile with linear interpolation between closest ranks
+    fn percentile(sorted_hours: &[f64], p: f64) -> f64 {
+        if sorted_hours.is_empty() {
+            return 0.0;
+        }
+
+        let rank = p / 100.0 * (sorted_hours.len() - 1) as f64;
+        let lower = rank.floor() as usize;
+        let upper = rank.ceil() as usize;
+        sorted_hours[lower] + (sorted_hours[upper] - sorted_hours[lower]) * (rank - lower as f64)
+    }
+
+    pub fn calculate_percentiles(&mut self) {
+        let mut sorted_hours = self.hours_list.clone();
+        sorted_hours.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        self.p25_hours = Self::percentile(&sorted_hours, 25.0);
+        self.p50_hours = Self::percentile(&sorted_hours, 50.0);
+        self.p75_hours = Self::percentile(&sorted_hours, 75.0);
+        self.p90_hours = Self::percentile(&sorted_hours, 90.0);
+    }
+}
+
+impl Default for DurationDistribution {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Time from first install to the first subscription, first cancel and uninstall
+#[derive(Debug, Clone, Getters, MutGetters, Setters, Serialize, Deserialize)]
+#[getset(get = "pub", get_mut = "pub", set = "pub")]
+pub struct TimeToEventStats {
+    to_first_subscription: DurationDistribution,
+    to_first_cancel: DurationDistribution,
+    to_uninstall: DurationDistribution,
+}
+
+impl TimeToEventStats {
+    pub fn new() -> Self {
+        Self {
+            to_first_subscription: DurationDistribution::new(),
+            to_first_cancel: DurationDistribution::new(),
+            to_uninstall: DurationDistribution::new(),
+        }
+    }
+
+    pub fn calculate_percentiles(&mut self) {
+        self.to_first_subscription.calculate_percentiles();
+        self.to_first_cancel.calculate_percentiles();
+        self.to_uninstall.calculate_percentiles();
+    }
+}
+
+impl Default for TimeToEventStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Getters, Setters, Serialize, Deserialize)]
+#[getset(get = "pub", set = "pub")]
+pub struct ReactivationStats {
+    reinstalled_count: u32,
+    reinstalled_merchant_count: u32,
+    average_reinstall_gap_days: f64,
+    median_reinstall_gap_days: f64,
+
+    resubscribed_count: u32,
+    resubscribed_merchant_count: u32,
+    average_resubscribe_gap_days: f64,
+    median_resubscribe_gap_days: f64,
+
+    /// Reactivations returning to the same plan as before leaving
+    same_plan_count: u32,
+
+    /// Reactivations returning to a different plan than before leaving
+    different_plan_count: u32,
+}
+
+impl ReactivationStats {
+    pub fn new() -> Self {
+        Self {
+            reinstalled_count: 0,
+            reinstalled_merchant_count: 0,
+            average_reinstall_gap_days: 0.0,
+            median_reinstall_gap_days: 0.0,
+            resubscribed_count: 0,
+            resubscribed_merchant_count: 0,
+            average_resubscribe_gap_days: 0.0,
+            median_resubscribe_gap_days: 0.0,
+            same_plan_count: 0,
+            different_plan_count: 0,
+        }
+    }
+
+    pub fn add_merchant(&mut self, merchant: &Merchant) {
+        let mut reinstalled = false;
+        let mut resubscribed = false;
+
+        for reactivation in merchant.reactivations() {
+            if reactivation.kind() == REACTIVATION_KIND_REINSTALL {
+                self.reinstalled_count += 1;
+                reinstalled = true;
+            } else {
+                self.resubscribed_count += 1;
+                resubscribed = true;
+            }
+
+            match reactivation.is_same_plan() {
+                Some(true) => self.same_plan_count += 1,
+                Some(false) => self.different_plan_count += 1,
+                None => {}
+            }
+        }
+
+        if reinstalled {
+            self.reinstalled_merchant_count += 1;
+        }
+        if resubscribed {
+            self.resubscribed_merchant_count += 1;
+        }
+    }
+}
+
+impl Default for ReactivationStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Getters, Setters, Serialize, Deserialize)]
+#[getset(get = "pub", set = "pub")]
+pub struct OneTimePackStats {
+    purchase_count: u32,
+    revenue: f64,
+    buyer_count: u32,
+
+    /// Buyers who purchased more than once
+    repeat_buyer_count: u32,
+
+    average_packs_per_buyer: f64,
+    average_days_between_purchases: f64,
+    median_days_between_purchases: f64,
+}
+
+impl OneTimePackStats {
+    pub fn new() -> Self {
+        Self {
+            purchase_count: 0,
+            revenue: 0.0,
+            buyer_count: 0,
+            repeat_buyer_count: 0,
+            average_packs_per_buyer: 0.0,
+            average_days_between_purchases: 0.0,
+            median_days_between_purchases: 0.0,
+        }
+    }
+
+    pub fn add_buyer(&mut self, purchase_count: u32, revenue: f64) {
+        self.purchase_count += purchase_count;
+        self.revenue += revenue;
+        self.buyer_count += 1;
+        if purchase_count > 1 {
+            self.repeat_buyer_count += 1;
+        }
+        self.average_packs_per_buyer = self.purchase_count as f64 / self.buyer_count as f64;
+    }
+}
+
+impl Default for OneTimePackStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Getters, Setters, Serialize, Deserialize)]
+#[getset(get = "pub", set = "pub")]
+pub struct CohortStats {
+    merchant_count: u32,
+    uninstalled_count: u32,
+    revenue: f64,
+    tenure_days: f64,
+
+    /// Revenue per merchant-month of tenure
+    monthly_arpu: f64,
+
+    /// Uninstalls per merchant-month of tenure
+    monthly_churn_rate: f64,
+
+    /// Monthly ARPU / monthly churn rate, or observed revenue per merchant if nobody churned yet
+    ltv_estimate: f64,
+}
+
+impl CohortStats {
+    pub fn new() -> Self {
+        Self {
+            merchant_count: 0,
+            uninstalled_count: 0,
+            revenue: 0.0,
+            tenure_days: 0.0,
+            monthly_arpu: 0.0,
+            monthly_churn_rate: 0.0,
+            ltv_estimate: 0.0,
+        }
+    }
+
+    pub fn add_merchant(&mut self, revenue: f64, tenure_days: f64, uninstalled: bool) {
+        self.merchant_count += 1;
+        self.revenue += revenue;
+        self.tenure_days += tenure_days;
+        if uninstalled {
+            self.uninstalled_count += 1;
+        }
+    }
+
+    pub fn calculate_ltv(&mut self) {
+        let tenure_months = self.tenure_days / DAYS_PER_MONTH;
+
+        if tenure_months > 0.0 {
+            self.monthly_arpu = self.revenue / tenure_months;
+            self.monthly_churn_rate = self.uninstalled_count as f64 / tenure_months;
+        }
+
+        self.ltv_estimate = if self.monthly_churn_rate > 0.0 {
+            self.monthly_arpu / self.monthly_churn_rate
+        } else if self.merchant_count > 0 {
+            self.revenue / self.merchant_count as f64
+        } else {
+            0.0
+        };
+    }
+}
+
+impl Default for CohortStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Getters, MutGetters, Setters, Serialize, Deserialize)]
+#[getset(get = "pub", set = "pub")]
+pub struct RevenueStats {
+    subscription_revenue: f64,
+    one_time_revenue: f64,
+    total_revenue: f64,
+
+    merchant_count: u32,
+    paying_merchant_count: u32,
+
+    /// Average revenue per merchant in the period
+    arpu: f64,
+
+    /// Average revenue per paying merchant in the period
+    arppu: f64,
+
+    average_tenure_days: f64,
+
+    /// LTV estimate of all merchants installed in the period
+    ltv_estimate: f64,
+
+    /// Install cohorts by month
+    #[getset(get = "pub", get_mut = "pub", set = "")]
+    cohorts: IndexMap<String, CohortStats>,
+}
+
+impl RevenueStats {
+    pub fn new() -> Self {
+        Self {
+            subscription_revenue: 0.0,
+            one_time_revenue: 0.0,
+            total_revenue: 0.0,
+            merchant_count: 0,
+            paying_merchant_count: 0,
+            arpu: 0.0,
+            arppu: 0.0,
+            average_tenure_days: 0.0,
+            ltv_estimate: 0.0,
+            cohorts: IndexMap::new(),
+        }
+    }
+
+    pub fn add_merchant(&mut self, merchant: &Merchant) {
+        self.subscription_revenue += merchant.subscription_revenue;
+        self.one_time_revenue += merchant.one_time_revenue;
+        self.total_revenue += merchant.lifetime_revenue;
+        self.average_tenure_days += merchant.tenure_days;
+        self.merchant_count += 1;
+        if merchant.lifetime_revenue > 0.0 {
+            self.paying_merchant_count += 1;
+        }
+    }
+
+    pub fn calculate_averages(&mut self) {
+        if self.merchant_count > 0 {
+            self.arpu = self.total_revenue / self.merchant_count as f64;
+            self.average_tenure_days /= self.merchant_count as f64;
+        }
+        if self.paying_merchant_count > 0 {
+            self.arppu = self.total_revenue / self.paying_merchant_count as f64;
+        }
+
+        let mut all_cohorts = CohortStats::new();
+        for cohort in self.cohorts.values_mut() {
+            cohort.calculate_ltv();
+            all_cohorts.merchant_count += cohort.merchant_count;
+            all_cohorts.uninstalled_count += cohort.uninstalled_count;
+            all_cohorts.revenue += cohort.revenue;
+            all_cohorts.tenure_days += cohort.tenure_days;
+        }
+        all_cohorts.calculate_ltv();
+        self.ltv_estimate = all_cohorts.ltv_estimate;
+    }
+}
+
+impl Default for RevenueStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Dimension used to group merchants in breakdown stats
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GroupingDimension {
+    /// Value of any CSV column, e.g. "Shop country"
+    Field(String),
+    EmailDomain,
+    /// First capture group (or whole match) of the pattern in shop name
+    ShopNamePattern(String),
+    /// Plan of the first subscription activated after installing
+    PlanAtInstall,
+    EverSubscribed,
+}
+
+impl GroupingDimension {
+    /// Parse a comma separated list like "country, email_domain, shop_name:^(test)"
+    pub fn parse_list(source: &str) -> Result<Vec<Self>> {
+        source
+            .split(DIMENSION_SEPARATOR)
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse())
+            .collect()
+    }
+}
+
+impl FromStr for GroupingDimension {
+    type Err = anyhow::Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        let source = source.trim();
+
+        if let Some(pattern) = source.strip_prefix(DIMENSION_SHOP_NAME_PREFIX) {
+            Regex::new(pattern)
+                .map_err(|e| anyhow!("{} {}: {}", INVALID_DIMENSION_MESSAGE, source, e))?;
+            return Ok(Self::ShopNamePattern(pattern.to_string()));
+        }
+
+        if let Some(field) = source.strip_prefix(DIMENSION_FIELD_PREFIX) {
+            return Ok(Self::Field(field.trim().to_string()));
+        }
+
+        match source {
+            "" => Err(anyhow!("{}", INVALID_DIMENSION_MESSAGE)),
+            DIMENSION_COUNTRY => Ok(Self::Field(SHOP_COUNTRY_FIELD.to_string())),
+            DIMENSION_EMAIL_DOMAIN => Ok(Self::EmailDomain),
+            DIMENSION_PLAN_AT_INSTALL => Ok(Self::PlanAtInstall),
+            DIMENSION_EVER_SUBSCRIBED => Ok(Self::EverSubscribed),
+            //  Other columns need the field prefix, so a typo is not taken as an empty column
+            field => KNOWN_FIELDS
+                .iter()
+                .find(|known| known.eq_ignore_ascii_case(field))
+                .map(|known| Self::Field(known.to_string()))
+                .ok_or(anyhow!("{}: {}", INVALID_DIMENSION_MESSAGE, field)),
+        }
+    }
+}
+
+impl fmt::Display for GroupingDimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Field(field) => write!(f, "{}", field),
+            Self::EmailDomain => write!(f, "{}", DIMENSION_EMAIL_DOMAIN),
+            Self::ShopNamePattern(pattern) => {
+                write!(f, "{}{}", DIMENSION_SHOP_NAME_PREFIX, pattern)
+            }
+            Self::PlanAtInstall => write!(f, "{}", DIMENSION_PLAN_AT_INSTALL),
+            Self::EverSubscribed => write!(f, "{}", DIMENSION_EVER_SUBSCRIBED),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Getters, Setters, Serialize, Deserialize)]
+#[getset(get = "pub", set = "pub")]
+pub struct BreakdownGroup {
+    stats: TotalStats,
+
+    /// Breakdown of this group by the next dimension, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    breakdown: Option<BreakdownStats>,
+}
+
+/// Stats of each group of merchants, grouped by a dimension (e.g. country)
+#[derive(Debug, Clone, Getters, Setters, Serialize, Deserialize)]
+#[getset(get = "pub", set = "pub")]
+pub struct BreakdownStats {
+    dimension: String,
+
+    #[getset(get = "pub", set = "")]
+    groups: IndexMap<String, BreakdownGroup>,
+}
+
+impl BreakdownStats {
+    pub fn new(dimension: String) -> Self {
+        Self {
+            dimension,
+            groups: IndexMap::new(),
+        }
+    }
+
+    pub fn insert_group(
+        &mut self,
+        group: String,
+        stats: TotalStats,
+        breakdown: Option<BreakdownStats>,
+    ) {
+        self.groups
+            .insert(group, BreakdownGroup { stats, breakdown });
+    }
+
+    /// Dimension names from this level down to the deepest one
+    pub fn dimensions(&self) -> Vec<String> {
+        let mut dimensions = vec![self.dimension.clone()];
+        if let Some(next) = self.groups.values().find_map(|g| g.breakdown.as_ref()) {
+            dimensions.extend(next.dimensions());
+        }
+        dimensions
+    }
+}
+
+/// Change of a metric from the base period to the current period
+#[derive(Debug, Clone, Getters, Setters, Serialize, Deserialize)]
+#[getset(get = "pub", set = "pub")]
+pub struct MetricChange {
+    base: f64,
+    current: f64,
+    change: f64,
+
+    /// None if the base value is 0
+    percent_change: Option<f64>,
+}
+
+impl MetricChange {
+    pub fn new(base: f64, current: f64) -> Self {
+        Self {
+            base,
+            current,
+            change: current - base,
+            percent_change: if base != 0.0 {
+                Some((current - base) / base.abs() * 100.0)
+            } else {
+                None
+            },
+        }
+    }
+}
+
+/// Changes of a plan's monthly and yearly subscription counts
+#[derive(Debug, Clone, Getters, Setters, Serialize, Deserialize)]
+#[getset(get = "pub", set = "pub")]
+pub struct PlanChange {
+    monthly: MetricChange,
+    yearly: MetricChange,
+}
+
+impl PlanChange {
+    pub fn new(
+        base: &SubscriptionStatsCounter,
+        current: &SubscriptionStatsCounter,
+        plan: &str,
+    ) -> Self {
+        let count = |counts: &IndexMap<String, i32>| *counts.get(plan).unwrap_or(&0) as f64;
+
+        Self {
+            monthly: MetricChange::new(
+                count(base.monthly_counts()),
+                count(current.monthly_counts()),
+            ),
+            yearly: MetricChange::new(count(base.yearly_counts()), count(current.yearly_counts())),
+        }
+    }
+}
+
+/// Comparison of total stats of two periods
+#[derive(Debug, Clone, Getters, MutGetters, Setters, Serialize, Deserialize)]
+#[getset(get = "pub", set = "pub")]
+pub struct StatsComparison {
+    base_start_time_str: String,
+    base_end_time_str: String,
+    current_start_time_str: String,
+    current_end_time_str: String,
+
+    /// Every numeric total stats field, nested fields are joined with "."
+    #[getset(get = "pub", get_mut = "pub", set = "")]
+    metrics: IndexMap<String, MetricChange>,
+
+    /// Counter (e.g. new_sub) -> plan code -> changes
+    #[getset(get = "pub", get_mut = "pub", set = "")]
+    sub_stats_details: IndexMap<String, IndexMap<String, PlanChange>>,
+}
+
+impl StatsComparison {
+    pub fn new(base: &TotalStats, current: &TotalStats) -> Self {
+        let mut sub_stats_details: IndexMap<String, IndexMap<String, PlanChange>> = IndexMap::new();
+
+        for (counter, base_counter, current_counter) in [
+            (
+                "new_sub",
+                &base.sub_stats_details.new_sub,
+                &current.sub_stats_details.new_sub,
+            ),
+            (
+                "canceled_sub",
+                &base.sub_stats_details.canceled_sub,
+                &current.sub_stats_details.canceled_sub,
+            ),
+            (
+                "sub_growth",
+                &base.sub_stats_details.sub_growth,
+                &current.sub_stats_details.sub_growth,
+            ),
+            (
+                "all_new_sub",
+                &base.sub_stats_details.all_new_sub,
+                &current.sub_stats_details.all_new_sub,
+            ),
+            (
+                "all_canceled_sub",
+                &base.sub_stats_details.all_canceled_sub,
+                &current.sub_stats_details.all_canceled_sub,
+            ),
+        ] {
+            //  Plans of both periods, pricing definitions may differ between runs
+            let mut plans: Vec<&String> = current_counter.monthly_counts().keys().collect();
+            for plan in base_counter.monthly_counts().keys() {
+                if !plans.contains(&plan) {
+                    plans.push(plan);
+                }
+            }
+
+            sub_stats_details.insert(
+                counter.to_string(),
+                plans
+                    .into_iter()
+                    .map(|plan| {
+                        (
+                            plan.clone(),
+                            PlanChange::new(base_counter, current_counter, plan),
+                        )
+                    })
+                    .collect(),
+            );
+        }
+
+        Self {
+            base_start_time_str: base.start_time_str.clone(),
+            base_end_time_str: base.end_time_str.clone(),
+            current_start_time_str: current.start_time_str.clone(),
+            current_end_time_str: current.end_time_str.clone(),
+            metrics: IndexMap::new(),
+            sub_stats_details,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Getters, Setters)]
+#[getset(get = "pub", set = "pub")]
+pub struct ExcludingDef {
+    excluding_field: String,
+    excluding_pattern: String,
+}
+
+/// Kind of event counted by analyzing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventCategory {
+    Installed,
+    Uninstalled,
+    StoreClosed,
+    StoreReopened,
+    SubscriptionActivated,
+    SubscriptionCanceled,
+
+    /// Paused charge, e.g. on failed billing, not counted as cancel
+    SubscriptionFrozen,
+    SubscriptionUnfrozen,
+    OneTimeActivated,
+}
+
+/// Event name of a category, exact or regex
+#[derive(Debug, Clone, Deserialize, Getters, Setters)]
+#[getset(get = "pub", set = "pub")]
+pub struct EventNameRule {
+    pattern: String,
+
+    /// Regex follows the case-sensitive regex option, exact names are always case-sensitive
+    #[serde(default)]
+    regex: bool,
+}
+
+/// Event names of each category, new event names are added without a release
+#[derive(Debug, Default, Deserialize, Getters, Setters)]
+#[getset(get = "pub", set = "pub")]
+#[serde(default)]
+pub struct EventVocabulary {
+    categories: IndexMap<EventCategory, Vec<EventNameRule>>,
+}
+
+impl EventVocabulary {
+    /// Set the category of the first matching rule in definition order,
+    /// events matching no rule are left without category
+    pub fn classify_events(
+        &self,
+        app_event_list: &mut [AppEvent],
+        case_sensitive_regex: bool,
+    ) -> Result<()> {
+        let mut matchers: Vec<(EventCategory, Regex)> = Vec::new();
+        for (category, rules) in &self.categories {
+            for rule in rules {
+                let pattern = match (rule.regex, case_sensitive_regex) {
+                    (false, _) => format!("^{}$", regex::escape(rule.pattern.trim())),
+                    (true, true) => rule.pattern.clone(),
+                    (true, false) => format!("(?i){}", rule.pattern),
+                };
+                let re = Regex::new(&pattern).map_err(|e| {
+                    anyhow!(
+                        "{}: {} ({})",
+                        INVALID_EVENT_PATTERN_MESSAGE,
+                        rule.pattern,
+                        e
+                    )
+                })?;
+                matchers.push((*category, re));
+            }
+        }
+
+        for event in app_event_list.iter_mut() {
+            event.category = matchers
+                .iter()
+                .find(|(_, re)| re.is_match(event.event.trim()))
+                .map(|(category, _)| *category);
+        }
+
+        Ok(())
+    }
+}
+
+/// Time of a value in the given format, in UTC when the format has an offset.
+/// A date only value is at midnight
+fn parse_time(value: &str, pattern: &str) -> Option<NaiveDateTime> {
+    DateTime::<FixedOffset>::parse_from_str(value, pattern)
+        .map(|date_time| date_time.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(value, pattern))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, pattern)
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+}
+
+/// Column names and date formats of event history CSV files of a source
+#[derive(Debug, Default, Deserialize, Getters, Setters)]
+#[getset(get = "pub", set = "pub")]
+#[serde(default)]
+pub struct InputProfile {
+    /// Header names of each known field, matched case-insensitively
+    columns: IndexMap<String, Vec<String>>,
+
+    /// Tried before the common formats of `DETECTED_TIME_PATTERNS`
+    time_formats: Vec<String>,
+    billing_on_formats: Vec<String>,
+}
+
+impl InputProfile {
+    /// Known field of a header, or the header itself if not mapped
+    pub fn field_of_header(&self, header: &str) -> String {
+        let header = header.trim();
+        self.columns
+            .iter()
+            .find(|(field, names)| {
+                field.eq_ignore_ascii_case(header)
+                    || names.iter().any(|n| n.trim().eq_ignore_ascii_case(header))
+            })
+            .map(|(field, _)| field.clone())
+            .unwrap_or_else(|| header.to_string())
+    }
+
+    /// Format of a column: the first given one that parses every non-empty value, else the common one that does.
+    /// Common formats reading the values differently, e.g. 01/02/2025 month or day first, are an error.
+    /// A column may hold dates only
+    pub fn detect_format(field: &str, formats: &[String], values: &[&str]) -> Result<String> {
+        let values: Vec<&str> = values
+            .iter()
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .collect();
+        let parse_all = |pattern: &str| -> Option<Vec<NaiveDateTime>> {
+            values.iter().map(|v| parse_time(v, pattern)).collect()
+        };
+
+        if let Some(pattern) = formats.iter().find(|pattern| parse_all(pattern).is_some()) {
+            return Ok(pattern.clone());
+        }
+
+        let candidates: Vec<(&str, Vec<NaiveDateTime>)> = DETECTED_TIME_PATTERNS
+            .iter()
+            .filter_map(|pattern| parse_all(pattern).map(|times| (*pattern, times)))
+            .collect();
+
+        let Some(((pattern, times), others)) = candidates.split_first() else {
+            return Err(anyhow!(
+                "{} {}: {}",
+                NO_DATE_FORMAT_MATCHED_MESSAGE,
+                field,
+                values.first().unwrap_or(&"")
+            ));
+        };
+
+        match others.iter().find(|(_, other_times)| other_times != times) {
+            Some((other_pattern, other_times)) => {
+                let value_index: usize = times
+                    .iter()
+                    .zip(other_times)
+                    .position(|(time, other_time)| time != other_time)
+                    .unwrap_or_default();
+                Err(anyhow!(
+                    "{} {}: {} ({} or {})",
+                    AMBIGUOUS_DATE_FORMAT_MESSAGE,
+                    field,
+                    values[value_index],
+                    pattern,
+                    other_pattern
+                ))
+            }
+            None => Ok(pattern.to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Getters, Setters)]
+#[getset(get = "pub", set = "pub")]
+pub struct PricingUnit {
+    code: String,
+    name: String,
+    regex_pattern: String,
+    price: f64,
+    currency: String,
+
+    /// Free plans never bill the merchant, even after the trial
+    #[serde(default)]
+    free: bool,
+
+    /// Length of the free trial granted on activation, 0 means no trial
+    #[serde(default)]
+    trial_days: u32,
+
+    /// Price charged per year for yearly billing, 12 times the price if not defined
+    #[serde(default)]
+    yearly_price: Option<f64>,
+}
+
+impl PricingUnit {
+    /// Plans marked as free or priced at 0 do not bring any revenue
+    pub fn is_paying(&self) -> bool {
+        !self.free && self.price > 0.0
+    }
+
+    /// Monthly recurring revenue of the plan
+    pub fn monthly_price(&self, billing_cycle: &BillingCycle) -> f64 {
+        match billing_cycle {
+            BillingCycle::Monthly => self.cycle_price(billing_cycle),
+            BillingCycle::Yearly => self.cycle_price(billing_cycle) / MONTHS_PER_YEAR,
+        }
+    }
+
+    /// Price charged for one billing cycle
+    pub fn cycle_price(&self, billing_cycle: &BillingCycle) -> f64 {
+        if !self.is_paying() {
+            return 0.0;
+        }
+
+        match billing_cycle {
+            BillingCycle::Monthly => self.price,
+            BillingCycle::Yearly => self.yearly_price.unwrap_or(self.price * MONTHS_PER_YEAR),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Getters, Setters)]
+#[getset(get = "pub", set = "pub")]
+pub struct PricingDefs {
+    subscriptions: Vec<PricingUnit>,
+    one_times: Vec<PricingUnit>,
+}
+
+impl PricingDefs {
+    pub fn paying_subscriptions(&self) -> Vec<PricingUnit> {
+        self.subscriptions
+            .iter()
+            .filter(|p| p.is_paying())
+            .cloned()
+            .collect()
+    }
+
+    pub fn free_subscriptions(&self) -> Vec<PricingUnit> {
+        self.subscriptions
+            .iter()
+            .filter(|p| !p.is_paying())
+            .cloned()
+            .collect()
+    }
+}
+
+#[derive(Debug, Setters, Getters, MutGetters, Serialize, Deserialize, Clone)]
+#[getset(get = "pub", get_mut = "pub", set = "pub")]
+pub struct SubscriptionStatsCounter {
+    monthly_counts: IndexMap<String, i32>,
+    yearly_counts: IndexMap<String, i32>,
+}
+
+impl SubscriptionStatsCounter {
+    pub fn new(subscription_plan_list: &Vec<PricingUnit>) -> Self {
+        let mut monthly_counts: IndexMap<String, i32> = IndexMap::new();
+        let mut yearly_counts: IndexMap<String, i32> = IndexMap::new();
+
+        for subscription_plan in subscription_plan_list.iter() {
+            monthly_counts.insert(subscription_plan.code.clone(), 0);
+            yearly_counts.insert(subscription_plan.code.clone(), 0);
+        }
+
+        Self {
+            monthly_counts: monthly_counts,
+            yearly_counts: yearly_counts,
+        }
+    }
+
+    pub fn increase(
+        &mut self,
+        subscription_plan: &PricingUnit,
+        billing_cycle: &BillingCycle,
+        count: i32,
+    ) -> anyhow::Result<()> {
+        match billing_cycle {
+            BillingCycle::Monthly => {
+                if let Some(entry) = self.monthly_counts.get_mut(&subscription_plan.code) {
+                    *entry += count;
+                    Ok(())
+                } else {
+                    Err(anyhow!(
+                        "[SubscriptionStatsCounter] Subscription plan code {} not found in initialized monthly count stats",
+                        subscription_plan.code
+                    ))
+                }
+            }
+
+            BillingCycle::Yearly => {
+                if let Some(entry) = self.yearly_counts.get_mut(&subscription_plan.code) {
+                    *entry += count;
+                    Ok(())
+                } else {
+                    Err(anyhow!(
+                        "[SubscriptionStatsCounter] Subscription plan code {} not found in initialized yearly count stats",
+                        subscription_plan.code
+                    ))
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Getters, MutGetters, Setters, Serialize, Deserialize, Clone)]
+#[getset(get = "pub", get_mut = "pub", set = "pub")]
+pub struct DetailedSubscriptionStats {
+    new_sub: SubscriptionStatsCounter,
+    canceled_sub: SubscriptionStatsCounter,
+    sub_growth: SubscriptionStatsCounter,
+    all_new_sub: SubscriptionStatsCounter,
+    all_canceled_sub: SubscriptionStatsCounter,
+}
+
+impl DetailedSubscriptionStats {
+    pub fn new(subscription_plan_list: &Vec<PricingUnit>) -> Self {
+        Self {
+            new_sub: SubscriptionStatsCounter::new(subscription_plan_list),
+            canceled_sub: SubscriptionStatsCounter::new(subscription_plan_list),
+            sub_growth: SubscriptionStatsCounter::new(subscription_plan_list),
+            all_new_sub: SubscriptionStatsCounter::new(subscription_plan_list),
+            all_canceled_sub: SubscriptionStatsCounter::new(subscription_plan_list),
+        }
+    }
+
+    /// Subscription growth = new subscriptions - canceled subscriptions, per plan and billing cycle
+    pub fn calculate_sub_growth(&mut self) {
+        let mut calculated_result: IndexMap<String, i32> = IndexMap::new();
+
+        //  Yearly
+        for (plan, new_count) in self.new_sub.yearly_counts() {
+            let canceled_count = self.canceled_sub.yearly_counts().get(plan).unwrap_or(&0);
+            calculated_result.insert(plan.to_string(), new_count - canceled_count);
+        }
+        self.sub_growth.set_yearly_counts(calculated_result.clone());
+
+        //  Monthly
+        calculated_result.clear();
+        for (plan, new_count) in self.new_sub.monthly_counts() {
+            let canceled_count = self.canceled_sub.monthly_counts().get(plan).unwrap_or(&0);
+            calculated_result.insert(plan.to_string(), new_count - canceled_count);
+        }
+        self.sub_growth.set_monthly_counts(calculated_result);
+    }
+}
+
+impl Default for DetailedSubscriptionStats {
+    fn default() -> Self {
+        Self::new(&Vec::new())
+    }
+}
+
+#[derive(Debug, Clone, Getters, Setters, Serialize, Deserialize)]
+#[getset(get = "pub", set = "pub")]
+pub struct AppEvent {
+    time: Option<NaiveDateTime>,
+    event: String,
+    details: String,
+    billing_on: Option<NaiveDateTime>,
+    shop_name: String,
+    shop_country: String,
+    shop_email: String,
+    shop_domain: String,
+    excluding_check_data: String,
+
+    /// Set by the event vocabulary before analyzing, None for events not counted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    category: Option<EventCategory>,
+
+    /// CSV columns not mapped to any field above
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    other_fields: IndexMap<String, String>,
+}
+
+impl AppEvent {
+    pub fn new() -> Self {
+        AppEvent {
+            time: None,
+            event: String::default(),
+            details: String::default(),
+            billing_on: None,
+            shop_name: String::default(),
+            shop_country: String::default(),
+            shop_email: String::default(),
+            shop_domain: String::default(),
+            excluding_check_data: String::default(),
+            category: None,
+            other_fields: IndexMap::new(),
+        }
+    }
+
+    pub fn is(&self, category: EventCategory) -> bool {
+        self.category == Some(category)
+    }
+
+    fn parse_time(
+        data_hash: &IndexMap<String, String>,
+        data_field: &str,
+        pattern: &str,
+    ) -> Result<NaiveDateTime, String> {
+        let time_string = data_hash
+            .get(data_field)
+            .ok_or_else(|| format!("Missing required field: {}", data_field))?;
+
+        parse_time(time_string, pattern).ok_or_else(|| {
+            format!(
+                "Invalid date format for field {}: {}",
+                data_field, time_string
+            )
+        })
+    }
+
+    pub fn from_indexmap(
+        source: &IndexMap<String, String>,
+        excluding_check_field: &str,
+        time_pattern: &str,
+        billing_on_pattern: &str,
+    ) -> Result<Self, String> {
+        let mut time = None;
+        let mut billing_on = None;
+
+        match Self::parse_time(source, TIME_FIELD, time_pattern) {
+            Ok(date_time) => time = Some(date_time),
+            Err(e) => println!("Warning: {}", e),
+        }
+
+        match Self::parse_time(source, BILLING_ON_FIELD, billing_on_pattern) {
+            Ok(date_time) => billing_on = Some(date_time),
+            Err(e) => println!("Warning: {}", e),
+        }
+
+        Ok(AppEvent {
+            time,
+            event: source.get(EVENT_FIELD).cloned().unwrap_or_default(),
             details: source.get(DETAILS_FIELD).cloned().unwrap_or_default(),
             billing_on,
             shop_name: source.get(SHOP_NAME_FIELD).cloned().unwrap_or_default(),
@@ -479,6 +2222,274 @@ impl AppEvent {
                 .get(excluding_check_field)
                 .cloned()
                 .unwrap_or_default(),
+            category: None,
+            other_fields: source
+                .iter()
+                .filter(|(k, _)| !KNOWN_FIELDS.contains(&k.as_str()))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
         })
     }
+
+    /// Value of the given CSV column
+    pub fn field_value(&self, field: &str) -> String {
+        match field {
+            EVENT_FIELD => self.event.clone(),
+            DETAILS_FIELD => self.details.clone(),
+            TIME_FIELD => self.time.map(|t| t.to_string()).unwrap_or_default(),
+            EMAIL_FIELD => self.shop_email.clone(),
+            BILLING_ON_FIELD => self.billing_on.map(|t| t.to_string()).unwrap_or_default(),
+            SHOP_DOMAIN_FIELD => self.shop_domain.clone(),
+            SHOP_NAME_FIELD => self.shop_name.clone(),
+            SHOP_COUNTRY_FIELD => self.shop_country.clone(),
+            _ => self.other_fields.get(field).cloned().unwrap_or_default(),
+        }
+    }
+}
+
+impl Default for AppEvent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definitions::default_event_vocabulary::SHOPIFY_EVENT_VOCABULARY_JSON_STRING;
+
+    #[test]
+    fn grouping_dimension_rejects_unknown_names() {
+        assert_eq!(
+            "country".parse::<GroupingDimension>().unwrap(),
+            GroupingDimension::Field(SHOP_COUNTRY_FIELD.to_string())
+        );
+        assert_eq!(
+            "shop name".parse::<GroupingDimension>().unwrap(),
+            GroupingDimension::Field(SHOP_NAME_FIELD.to_string())
+        );
+        assert_eq!(
+            "field:Plan tier".parse::<GroupingDimension>().unwrap(),
+            GroupingDimension::Field("Plan tier".to_string())
+        );
+
+        let error = "contry".parse::<GroupingDimension>().unwrap_err();
+        assert!(error.to_string().starts_with(INVALID_DIMENSION_MESSAGE));
+        assert!(GroupingDimension::parse_list("country, contry").is_err());
+    }
+
+    #[test]
+    fn date_format_detection_rejects_ambiguous_columns() {
+        let no_formats: Vec<String> = Vec::new();
+
+        //  Month or day first
+        let error =
+            InputProfile::detect_format(TIME_FIELD, &no_formats, &["01/02/2025", "03/04/2025"])
+                .unwrap_err();
+        assert!(error.to_string().starts_with(AMBIGUOUS_DATE_FORMAT_MESSAGE));
+
+        //  Set by the profile
+        let pinned_formats = vec!["%d/%m/%Y".to_string()];
+        assert_eq!(
+            InputProfile::detect_format(TIME_FIELD, &pinned_formats, &["01/02/2025"]).unwrap(),
+            "%d/%m/%Y"
+        );
+
+        //  A day above 12 leaves one reading, a date read the same both ways is no conflict
+        assert_eq!(
+            InputProfile::detect_format(TIME_FIELD, &no_formats, &["01/02/2025", "13/02/2025"])
+                .unwrap(),
+            "%d/%m/%Y"
+        );
+        assert_eq!(
+            InputProfile::detect_format(TIME_FIELD, &no_formats, &["01/01/2025"]).unwrap(),
+            "%m/%d/%Y"
+        );
+
+        assert!(InputProfile::detect_format(TIME_FIELD, &no_formats, &["yesterday"]).is_err());
+    }
+
+    #[test]
+    fn event_time_with_offset_is_kept_in_utc() {
+        let values = ["2025-01-01T10:00:00+07:00", "2025-01-01T23:30:00.5-02:00"];
+        let pattern = InputProfile::detect_format(TIME_FIELD, &[], &values).unwrap();
+
+        let times: Vec<Option<NaiveDateTime>> = values
+            .iter()
+            .map(|value| {
+                let mut record: IndexMap<String, String> = IndexMap::new();
+                record.insert(TIME_FIELD.to_string(), value.to_string());
+                *AppEvent::from_indexmap(&record, "", &pattern, BILLING_ON_PATTERN)
+                    .unwrap()
+                    .time()
+            })
+            .collect();
+
+        assert_eq!(
+            times,
+            vec![
+                NaiveDateTime::parse_from_str("2025-01-01 03:00:00", HISTORY_TIME_PATTERN).ok(),
+                NaiveDateTime::parse_from_str("2025-01-02 01:30:00.5", "%Y-%m-%d %H:%M:%S%.f").ok(),
+            ]
+        );
+    }
+
+    fn classify(
+        vocabulary_json: &str,
+        event_names: &[&str],
+        case_sensitive_regex: bool,
+    ) -> Result<Vec<Option<EventCategory>>> {
+        let vocabulary: EventVocabulary = serde_json::from_str(vocabulary_json).unwrap();
+        let mut app_event_list: Vec<AppEvent> = event_names
+            .iter()
+            .map(|name| {
+                let mut event = AppEvent::new();
+                event.set_event(name.to_string());
+                event
+            })
+            .collect();
+
+        vocabulary.classify_events(&mut app_event_list, case_sensitive_regex)?;
+        Ok(app_event_list.iter().map(|e| *e.category()).collect())
+    }
+
+    #[test]
+    fn shopify_vocabulary_tells_similar_names_apart() {
+        let categories = classify(
+            SHOPIFY_EVENT_VOCABULARY_JSON_STRING,
+            &[
+                "Uninstalled",
+                "Installed",
+                "Charge activated",
+                "Subscription charge activated",
+                "Subscription charge unfrozen",
+                "Usage charge applied",
+            ],
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(
+            categories,
+            vec![
+                Some(EventCategory::Uninstalled),
+                Some(EventCategory::Installed),
+                Some(EventCategory::OneTimeActivated),
+                Some(EventCategory::SubscriptionActivated),
+                Some(EventCategory::SubscriptionUnfrozen),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn event_name_rules_follow_case_option_for_regex_only() {
+        let vocabulary = r#"{ "categories": {
+            "installed": [{ "pattern": "install", "regex": true }],
+            "uninstalled": [{ "pattern": "Removed" }]
+        }}"#;
+        let event_names = ["App INSTALLED", "Removed", "removed"];
+
+        assert_eq!(
+            classify(vocabulary, &event_names, false).unwrap(),
+            vec![
+                Some(EventCategory::Installed),
+                Some(EventCategory::Uninstalled),
+                None
+            ]
+        );
+        assert_eq!(
+            classify(vocabulary, &event_names, true).unwrap(),
+            vec![None, Some(EventCategory::Uninstalled), None]
+        );
+    }
+
+    #[test]
+    fn first_matching_event_name_rule_wins() {
+        let uninstalled_first = r#"{ "categories": {
+            "uninstalled": [{ "pattern": "uninstall", "regex": true }],
+            "installed": [{ "pattern": "install", "regex": true }]
+        }}"#;
+        let installed_first = r#"{ "categories": {
+            "installed": [{ "pattern": "install", "regex": true }],
+            "uninstalled": [{ "pattern": "uninstall", "regex": true }]
+        }}"#;
+
+        assert_eq!(
+            classify(uninstalled_first, &["App uninstalled"], false).unwrap(),
+            vec![Some(EventCategory::Uninstalled)]
+        );
+        assert_eq!(
+            classify(installed_first, &["App uninstalled"], false).unwrap(),
+            vec![Some(EventCategory::Installed)]
+        );
+    }
+
+    #[test]
+    fn invalid_event_name_pattern_is_an_error() {
+        let vocabulary = r#"{ "categories": {
+            "installed": [{ "pattern": "(installed", "regex": true }]
+        }}"#;
+
+        let error = classify(vocabulary, &["Installed"], false).unwrap_err();
+        assert!(error.to_string().starts_with(INVALID_EVENT_PATTERN_MESSAGE));
+    }
+
+    #[test]
+    fn output_names_fill_placeholders_and_drop_empty_ones() {
+        let out_folder = std::env::temp_dir().join(format!("jisrot_naming_{}", std::process::id()));
+        let start = NaiveDateTime::parse_from_str("2025-01-01 00:00", "%Y-%m-%d %H:%M").ok();
+        let end = NaiveDateTime::parse_from_str("2025-07-31 00:00", "%Y-%m-%d %H:%M").ok();
+
+        let naming = OutputNaming::new(&out_folder, "  ", "app", &OverwritePolicy::Overwrite);
+        assert_eq!(
+            naming.out_file("total_stats", &start, &end, "json"),
+            out_folder.join("app_total_stats_2025-01-01_2025-07-31.json")
+        );
+        assert_eq!(
+            naming.out_file("total_stats", &None, &end, ""),
+            out_folder.join("app_total_stats_2025-07-31")
+        );
+
+        let naming = OutputNaming::new(
+            &out_folder,
+            "{data}_{start}-{end}_{app}",
+            "",
+            &OverwritePolicy::Overwrite,
+        );
+        assert_eq!(
+            naming.out_file("a/b", &start, &end, "csv"),
+            out_folder.join("a-b_Jan01-Jul31.csv")
+        );
+    }
+
+    #[test]
+    fn overwrite_policy_decides_on_existing_files() {
+        let out_folder =
+            std::env::temp_dir().join(format!("jisrot_overwrite_{}", std::process::id()));
+        std::fs::create_dir_all(&out_folder).unwrap();
+        std::fs::write(out_folder.join("app_stats.json"), "").unwrap();
+        std::fs::write(out_folder.join("app_stats_2.json"), "").unwrap();
+
+        let out_file = |policy: &OverwritePolicy| {
+            let naming = OutputNaming::new(&out_folder, "{app}_{data}", "app", policy);
+            let out_file = naming.out_file("stats", &None, &None, "json");
+            (naming.skips(&out_file), out_file)
+        };
+
+        assert_eq!(
+            out_file(&OverwritePolicy::Overwrite),
+            (false, out_folder.join("app_stats.json"))
+        );
+        assert_eq!(
+            out_file(&OverwritePolicy::Skip),
+            (true, out_folder.join("app_stats.json"))
+        );
+        assert_eq!(
+            out_file(&OverwritePolicy::Suffix),
+            (false, out_folder.join("app_stats_3.json"))
+        );
+
+        std::fs::remove_dir_all(&out_folder).unwrap();
+    }
 }
@@ -1,8 +1,11 @@
 use std::borrow::Cow;
+use std::path::PathBuf;
 
 use getset::Getters;
 use serde::{Deserialize, Serialize};
 
+use crate::models::data_model::{ChurnFormula, OverwritePolicy};
+
 #[derive(Getters, Clone, Deserialize, Serialize, PartialEq)]
 #[getset(get = "pub")]
 pub struct UiOption {
@@ -10,3 +13,37 @@ pub struct UiOption {
     pub text: Cow<'static, str>,
     pub connected_data: Option<Cow<'static, str>>,
 }
+
+/// Choices of an analysis run, made in GUI or given as command line arguments.
+/// Custom definition files are read when their option is the custom one
+#[derive(Clone, Copy)]
+pub struct AnalysisRequest<'a> {
+    pub event_history_file_list: &'a Option<Vec<PathBuf>>,
+    pub selected_pricing_defs_option: &'a UiOption,
+    pub pricing_defs_file: &'a Option<PathBuf>,
+    pub selected_excluding_defs_option: &'a UiOption,
+    pub excluding_defs_file: &'a Option<PathBuf>,
+    pub selected_input_profile_option: &'a UiOption,
+    pub input_profile_file: &'a Option<PathBuf>,
+    pub selected_event_vocabulary_option: &'a UiOption,
+    pub event_vocabulary_file: &'a Option<PathBuf>,
+    pub selected_report_template_option: &'a UiOption,
+    pub report_template_file: &'a Option<PathBuf>,
+    pub opening_state_file: &'a Option<PathBuf>,
+    pub out_folder: &'a Option<PathBuf>,
+    pub out_file_name_template: &'a str,
+    pub overwrite_policy: &'a OverwritePolicy,
+
+    /// Empty to not keep history
+    pub history_app: &'a str,
+
+    /// Start and end dates to analyze the history over instead of the chosen files
+    pub history_period: Option<(&'a str, &'a str)>,
+    /// Start and end dates of the history period to compare with
+    pub comparison_period: Option<(&'a str, &'a str)>,
+    pub debug_mode: bool,
+    pub output_formats: &'a [String],
+    pub case_sensitive_regex: bool,
+    pub breakdown_dimensions: &'a str,
+    pub churn_formula: &'a ChurnFormula,
+}
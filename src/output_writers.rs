@@ -0,0 +1,526 @@
+use getset::CopyGetters;
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock, RwLock};
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::analyzing::compare_total_stats;
+use crate::data_io::*;
+use crate::definitions::common::*;
+use crate::definitions::strings::*;
+use crate::models::data_model::*;
+
+/// Data of an analysis run handed to output writers
+#[derive(CopyGetters)]
+#[getset(get_copy = "pub")]
+pub struct OutputContext<'a> {
+    total_stats: &'a TotalStats,
+    merchant_data: &'a MerchantList,
+
+    /// Raw events, given in debug mode only
+    app_event_list: Option<&'a Vec<AppEvent>>,
+
+    /// Given when breakdown dimensions are chosen
+    breakdown_stats: Option<&'a BreakdownStats>,
+
+    /// Name and source of the report template, given when a template is chosen
+    report_template: Option<(&'a str, &'a str)>,
+
+    /// Total stats of the base period, given when comparing periods
+    comparison_base: Option<&'a TotalStats>,
+}
+
+impl<'a> OutputContext<'a> {
+    pub fn new(
+        total_stats: &'a TotalStats,
+        merchant_data: &'a MerchantList,
+        app_event_list: Option<&'a Vec<AppEvent>>,
+        breakdown_stats: Option<&'a BreakdownStats>,
+        report_template: Option<(&'a str, &'a str)>,
+        comparison_base: Option<&'a TotalStats>,
+    ) -> Self {
+        Self {
+            total_stats,
+            merchant_data,
+            app_event_list,
+            breakdown_stats,
+            report_template,
+            comparison_base,
+        }
+    }
+}
+
+/// Output format of analysis results, selected by name.
+/// Writers of other crates are added with `register_output_writer`
+pub trait OutputWriter: Send + Sync {
+    /// Unique name to select the format by, e.g. "csv"
+    fn name(&self) -> &str;
+
+    /// Written on every run whatever the chosen formats, not shown as a choice in GUI.
+    /// Such a writer writes nothing when the run has no data for it
+    fn always_written(&self) -> bool {
+        false
+    }
+
+    /// Text of the format in GUI
+    fn label(&self) -> String {
+        self.name().to_uppercase()
+    }
+
+    /// Write the output files of a run, return the success message
+    fn write(
+        &self,
+        output_naming: &OutputNaming,
+        context: &OutputContext,
+    ) -> anyhow::Result<String>;
+}
+
+/// Total stats as JSON, with breakdown stats if any, and merchant data and raw events in debug mode.
+/// Written on every run, as summaries, comparisons and opening states are read from these files
+pub struct JsonWriter;
+
+impl JsonWriter {
+    fn write_data(
+        output_naming: &OutputNaming,
+        context: &OutputContext,
+        data_name: &str,
+        data: &(impl Serialize + ?Sized),
+    ) -> anyhow::Result<String> {
+        write_output(
+            output_naming,
+            data_name,
+            &data_name.to_string().replace(" ", "_").to_lowercase(),
+            context.total_stats().start_time(),
+            context.total_stats().end_time(),
+            OUTPUT_FORMAT_JSON,
+            |f| write_to_json(f, data),
+        )
+    }
+}
+
+impl OutputWriter for JsonWriter {
+    fn name(&self) -> &str {
+        OUTPUT_FORMAT_JSON
+    }
+
+    fn always_written(&self) -> bool {
+        true
+    }
+
+    fn write(
+        &self,
+        output_naming: &OutputNaming,
+        context: &OutputContext,
+    ) -> anyhow::Result<String> {
+        let mut message_success: String = Self::write_data(
+            output_naming,
+            context,
+            data::TOTAL_STATS,
+            context.total_stats(),
+        )?;
+
+        if let Some(breakdown_stats) = context.breakdown_stats() {
+            message_success += Self::write_data(
+                output_naming,
+                context,
+                data::BREAKDOWN_STATS,
+                breakdown_stats,
+            )?
+            .as_str();
+        }
+
+        if let Some(app_event_list) = context.app_event_list() {
+            message_success += Self::write_data(
+                output_naming,
+                context,
+                data::MERCHANT_DATA,
+                context.merchant_data(),
+            )?
+            .as_str();
+            message_success +=
+                Self::write_data(output_naming, context, data::APP_EVENTS, app_event_list)?
+                    .as_str();
+        }
+
+        Ok(message_success)
+    }
+}
+
+/// Total stats in long format and merchant data as CSV
+pub struct CsvWriter;
+
+impl OutputWriter for CsvWriter {
+    fn name(&self) -> &str {
+        OUTPUT_FORMAT_CSV
+    }
+
+    fn write(
+        &self,
+        output_naming: &OutputNaming,
+        context: &OutputContext,
+    ) -> anyhow::Result<String> {
+        let total_stats = context.total_stats();
+
+        Ok(write_output(
+            output_naming,
+            data::TOTAL_STATS,
+            &data::TOTAL_STATS
+                .to_string()
+                .replace(" ", "_")
+                .to_lowercase(),
+            total_stats.start_time(),
+            total_stats.end_time(),
+            OUTPUT_FORMAT_CSV,
+            |f| write_total_stats_to_csv(f, total_stats),
+        )? + write_output(
+            output_naming,
+            data::MERCHANT_DATA,
+            &data::MERCHANT_DATA
+                .to_string()
+                .replace(" ", "_")
+                .to_lowercase(),
+            total_stats.start_time(),
+            total_stats.end_time(),
+            OUTPUT_FORMAT_CSV,
+            |f| write_merchant_data_to_csv(f, context.merchant_data()),
+        )?
+        .as_str())
+    }
+}
+
+/// Workbook report, with raw events in debug mode
+pub struct XlsxWriter;
+
+impl OutputWriter for XlsxWriter {
+    fn name(&self) -> &str {
+        OUTPUT_FORMAT_XLSX
+    }
+
+    fn write(
+        &self,
+        output_naming: &OutputNaming,
+        context: &OutputContext,
+    ) -> anyhow::Result<String> {
+        let total_stats = context.total_stats();
+
+        write_output(
+            output_naming,
+            data::REPORT,
+            &data::REPORT.to_string().replace(" ", "_").to_lowercase(),
+            total_stats.start_time(),
+            total_stats.end_time(),
+            OUTPUT_FORMAT_XLSX,
+            |f| {
+                write_report_to_xlsx(
+                    f,
+                    total_stats,
+                    context.merchant_data(),
+                    context.app_event_list(),
+                )
+            },
+        )
+    }
+}
+
+/// Self-contained HTML report
+pub struct HtmlWriter;
+
+impl OutputWriter for HtmlWriter {
+    fn name(&self) -> &str {
+        OUTPUT_FORMAT_HTML
+    }
+
+    fn write(
+        &self,
+        output_naming: &OutputNaming,
+        context: &OutputContext,
+    ) -> anyhow::Result<String> {
+        let total_stats = context.total_stats();
+
+        write_output(
+            output_naming,
+            data::HTML_REPORT,
+            &data::REPORT.to_string().replace(" ", "_").to_lowercase(),
+            total_stats.start_time(),
+            total_stats.end_time(),
+            OUTPUT_FORMAT_HTML,
+            |f| write_report_to_html(f, total_stats, context.merchant_data()),
+        )
+    }
+}
+
+/// Report rendered from the chosen template, named and typed after the template.
+/// E.g. summary.txt gives a summary .txt file
+pub struct TemplateReportWriter;
+
+impl OutputWriter for TemplateReportWriter {
+    fn name(&self) -> &str {
+        OUTPUT_FORMAT_TEMPLATE_REPORT
+    }
+
+    fn always_written(&self) -> bool {
+        true
+    }
+
+    fn write(
+        &self,
+        output_naming: &OutputNaming,
+        context: &OutputContext,
+    ) -> anyhow::Result<String> {
+        let Some((template_name, template_source)) = context.report_template() else {
+            return Ok(String::new());
+        };
+        let total_stats = context.total_stats();
+        let template_path: PathBuf = PathBuf::from(template_name);
+
+        write_output(
+            output_naming,
+            data::TEMPLATE_REPORT,
+            &template_path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy(),
+            total_stats.start_time(),
+            total_stats.end_time(),
+            &template_path
+                .extension()
+                .unwrap_or_default()
+                .to_string_lossy(),
+            |f| {
+                write_template_report(
+                    f,
+                    template_name,
+                    template_source,
+                    total_stats,
+                    context.merchant_data(),
+                )
+            },
+        )
+    }
+}
+
+/// Comparison of total stats with the base period as JSON and CSV
+pub struct StatsComparisonWriter;
+
+impl OutputWriter for StatsComparisonWriter {
+    fn name(&self) -> &str {
+        OUTPUT_FORMAT_STATS_COMPARISON
+    }
+
+    fn always_written(&self) -> bool {
+        true
+    }
+
+    fn write(
+        &self,
+        output_naming: &OutputNaming,
+        context: &OutputContext,
+    ) -> anyhow::Result<String> {
+        let Some(base_stats) = context.comparison_base() else {
+            return Ok(String::new());
+        };
+        let current_stats = context.total_stats();
+        let stats_comparison = compare_total_stats(base_stats, current_stats)?;
+        let format_date = |time: &Option<NaiveDateTime>| {
+            time.map(|t| t.format(HISTORY_DATE_PATTERN).to_string())
+                .unwrap_or_default()
+        };
+        let out_file_pref: String = format!(
+            "{}_vs_{}_{}",
+            data::STATS_COMPARISON.replace(" ", "_").to_lowercase(),
+            format_date(base_stats.start_time()),
+            format_date(base_stats.end_time())
+        );
+
+        Ok(write_output(
+            output_naming,
+            data::STATS_COMPARISON,
+            &out_file_pref,
+            current_stats.start_time(),
+            current_stats.end_time(),
+            OUTPUT_FORMAT_JSON,
+            |f| write_to_json(f, &stats_comparison),
+        )? + write_output(
+            output_naming,
+            data::STATS_COMPARISON,
+            &out_file_pref,
+            current_stats.start_time(),
+            current_stats.end_time(),
+            OUTPUT_FORMAT_CSV,
+            |f| write_stats_comparison_to_csv(f, &stats_comparison),
+        )?
+        .as_str())
+    }
+}
+
+/// Breakdown stats as CSV, one row per group, written with the breakdown JSON whatever the chosen formats
+pub struct BreakdownCsvWriter;
+
+impl OutputWriter for BreakdownCsvWriter {
+    fn name(&self) -> &str {
+        OUTPUT_FORMAT_BREAKDOWN_CSV
+    }
+
+    fn always_written(&self) -> bool {
+        true
+    }
+
+    fn write(
+        &self,
+        output_naming: &OutputNaming,
+        context: &OutputContext,
+    ) -> anyhow::Result<String> {
+        let Some(breakdown_stats) = context.breakdown_stats() else {
+            return Ok(String::new());
+        };
+        let total_stats = context.total_stats();
+
+        write_output(
+            output_naming,
+            data::BREAKDOWN_STATS,
+            &data::BREAKDOWN_STATS
+                .to_string()
+                .replace(" ", "_")
+                .to_lowercase(),
+            total_stats.start_time(),
+            total_stats.end_time(),
+            OUTPUT_FORMAT_CSV,
+            |f| write_breakdown_stats_to_csv(f, breakdown_stats),
+        )
+    }
+}
+
+static OUTPUT_WRITERS: LazyLock<RwLock<Vec<Arc<dyn OutputWriter>>>> = LazyLock::new(|| {
+    RwLock::new(vec![
+        Arc::new(JsonWriter),
+        Arc::new(CsvWriter),
+        Arc::new(XlsxWriter),
+        Arc::new(HtmlWriter),
+        Arc::new(TemplateReportWriter),
+        Arc::new(StatsComparisonWriter),
+        Arc::new(BreakdownCsvWriter),
+    ])
+});
+
+/// Add an output writer, it replaces the registered one of the same name
+pub fn register_output_writer(writer: Arc<dyn OutputWriter>) {
+    let mut writers = OUTPUT_WRITERS.write().unwrap();
+    match writers.iter().position(|w| w.name() == writer.name()) {
+        Some(i) => writers[i] = writer,
+        None => writers.push(writer),
+    }
+}
+
+pub fn output_writer(name: &str) -> Option<Arc<dyn OutputWriter>> {
+    OUTPUT_WRITERS
+        .read()
+        .unwrap()
+        .iter()
+        .find(|w| w.name() == name)
+        .cloned()
+}
+
+/// Registered output writers in registering order
+pub fn output_writers() -> Vec<Arc<dyn OutputWriter>> {
+    OUTPUT_WRITERS.read().unwrap().clone()
+}
+
+/// Write a data kind to its output file of the naming scheme, return the success message
+pub fn write_output(
+    output_naming: &OutputNaming,
+    data_name: &str,
+    out_file_pref: &str,
+    start_time: &Option<NaiveDateTime>,
+    end_time: &Option<NaiveDateTime>,
+    extension: &str,
+    write: impl FnOnce(&PathBuf) -> anyhow::Result<()>,
+) -> anyhow::Result<String> {
+    let out_file: PathBuf = output_naming.out_file(out_file_pref, start_time, end_time, extension);
+
+    if output_naming.skips(&out_file) {
+        return Ok(format!(
+            "{} {} {}\n",
+            data_name,
+            message::success::SPECIFIC_DATA_SKIPPED_FILE,
+            out_file.display()
+        ));
+    }
+
+    write(&out_file)?;
+
+    Ok(format!(
+        "{} {} {}\n",
+        data_name,
+        message::success::SPECIFIC_DATA_WRITTEN_FILE,
+        out_file.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_io::read_pricing_def_from_json_str;
+    use crate::definitions::default_ms_pricing_def::SBM_PRICING_DEF_JSON_STRING;
+
+    struct NamedWriter(&'static str, &'static str);
+
+    impl OutputWriter for NamedWriter {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn write(&self, _: &OutputNaming, _: &OutputContext) -> anyhow::Result<String> {
+            Ok(self.1.to_string())
+        }
+    }
+
+    #[test]
+    fn registered_writer_replaces_the_one_of_the_same_name() {
+        register_output_writer(Arc::new(NamedWriter("test_replaced", "first")));
+        register_output_writer(Arc::new(NamedWriter("test_replaced", "second")));
+
+        let writers = output_writers();
+        assert_eq!(
+            writers
+                .iter()
+                .filter(|w| w.name() == "test_replaced")
+                .count(),
+            1
+        );
+        assert_eq!(writers[0].name(), OUTPUT_FORMAT_JSON);
+        assert!(
+            writers
+                .iter()
+                .filter(|w| w.always_written())
+                .map(|w| w.name())
+                .eq([
+                    OUTPUT_FORMAT_JSON,
+                    OUTPUT_FORMAT_TEMPLATE_REPORT,
+                    OUTPUT_FORMAT_STATS_COMPARISON,
+                    OUTPUT_FORMAT_BREAKDOWN_CSV,
+                ])
+        );
+    }
+
+    #[test]
+    fn writers_of_optional_data_write_nothing_without_it() {
+        let pricing_defs = read_pricing_def_from_json_str(SBM_PRICING_DEF_JSON_STRING).unwrap();
+        let total_stats = TotalStats::new(&pricing_defs);
+        let merchant_data = MerchantList::new();
+        let out_folder =
+            std::env::temp_dir().join(format!("jisrot_optional_outputs_{}", std::process::id()));
+        let output_naming = OutputNaming::new(&out_folder, "", "", &OverwritePolicy::Overwrite);
+        let context = OutputContext::new(&total_stats, &merchant_data, None, None, None, None);
+
+        for name in [
+            OUTPUT_FORMAT_TEMPLATE_REPORT,
+            OUTPUT_FORMAT_STATS_COMPARISON,
+            OUTPUT_FORMAT_BREAKDOWN_CSV,
+        ] {
+            let writer = output_writer(name).unwrap();
+            assert_eq!(writer.write(&output_naming, &context).unwrap(), "");
+        }
+        assert!(!out_folder.exists());
+    }
+}
@@ -0,0 +1,618 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use indexmap::IndexMap;
+use std::fmt::Write;
+
+use crate::definitions::common::*;
+use crate::definitions::strings::report::*;
+use crate::models::data_model::*;
+
+const CHART_WIDTH: f64 = 760.0;
+const CHART_HEIGHT: f64 = 260.0;
+const CHART_MARGIN_LEFT: f64 = 40.0;
+const CHART_MARGIN_RIGHT: f64 = 10.0;
+const CHART_MARGIN_TOP: f64 = 10.0;
+const CHART_MARGIN_BOTTOM: f64 = 40.0;
+const CHART_Y_TICKS: u32 = 4;
+const CHART_MAX_X_LABELS: usize = 12;
+
+/// Periods longer than this are charted by week instead of by day
+const DAILY_TREND_MAX_DAYS: i64 = 62;
+
+const CHART_COLORS: [&str; 4] = ["#2b7bb9", "#d9534f", "#5cb85c", "#f0ad4e"];
+
+const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', Roboto, Helvetica, Arial, sans-serif; color: #222; margin: 24px auto; max-width: 960px; padding: 0 16px; }
+h1 { margin-bottom: 0; }
+h2 { margin-top: 32px; border-bottom: 1px solid #ddd; padding-bottom: 4px; }
+.period { color: #666; margin-top: 4px; }
+.cards { display: flex; flex-wrap: wrap; gap: 12px; }
+.card { border: 1px solid #ddd; border-radius: 6px; padding: 10px 14px; min-width: 130px; }
+.card .label { color: #666; font-size: 12px; }
+.card .value { font-size: 22px; font-weight: 600; }
+table { border-collapse: collapse; margin-top: 8px; }
+th, td { border: 1px solid #ddd; padding: 4px 10px; text-align: right; }
+th:first-child, td:first-child { text-align: left; }
+th { background: #f5f5f5; }
+svg text { font-size: 11px; fill: #444; }
+.legend span { display: inline-block; margin-right: 14px; font-size: 12px; }
+.legend i { display: inline-block; width: 10px; height: 10px; margin-right: 4px; }
+";
+
+/// Render a self-contained HTML report, no external resource is needed to view it
+pub fn render_html_report(total_stats: &TotalStats, merchant_list: &MerchantList) -> String {
+    let mut html = String::new();
+
+    let _ = writeln!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{} {} - {}</title>\n<style>{}</style>\n</head>\n<body>",
+        TITLE,
+        escape_html(total_stats.start_time_str()),
+        escape_html(total_stats.end_time_str()),
+        STYLE
+    );
+    let _ = writeln!(
+        html,
+        "<h1>{}</h1>\n<p class=\"period\">{}</p>",
+        TITLE,
+        escape_html(&period_text(total_stats))
+    );
+
+    html += &headline_section(total_stats);
+    html += &trend_section(total_stats, merchant_list);
+    html += &subscription_section(total_stats);
+    html += &one_time_section(total_stats);
+    html += &churn_section(total_stats);
+
+    html += "</body>\n</html>\n";
+    html
+}
+
+fn headline_section(total_stats: &TotalStats) -> String {
+    let cards: Vec<(String, String)> = vec![
+        (
+            CARD_INSTALLED.to_string(),
+            total_stats.installed_count().to_string(),
+        ),
+        (
+            CARD_UNINSTALLED.to_string(),
+            total_stats.uninstalled_count().to_string(),
+        ),
+        (
+            CARD_MERCHANT_GROWTH.to_string(),
+            total_stats.merchant_growth().to_string(),
+        ),
+        (
+            CARD_STORE_CLOSURE_GROWTH.to_string(),
+            total_stats.store_closure_growth().to_string(),
+        ),
+        (
+            CARD_NEW_PAYING_SUB.to_string(),
+            total_stats.new_paying_sub_count().to_string(),
+        ),
+        (
+            CARD_CANCELED_PAYING_SUB.to_string(),
+            total_stats.canceled_paying_sub_count().to_string(),
+        ),
+        (
+            CARD_ONE_TIME.to_string(),
+            total_stats.one_time_count().to_string(),
+        ),
+        (
+            format!(
+                "{} ({})",
+                CARD_HEADLINE_CHURN,
+                total_stats.headline_churn_formula()
+            ),
+            format_percent(*total_stats.headline_churn_rate()),
+        ),
+        (
+            CARD_REVENUE.to_string(),
+            format_money(*total_stats.revenue_stats().total_revenue()),
+        ),
+    ];
+
+    let mut html = format!("<h2>{}</h2>\n<div class=\"cards\">\n", SECTION_HEADLINE);
+    for (label, value) in cards {
+        let _ = writeln!(
+            html,
+            "<div class=\"card\"><div class=\"label\">{}</div><div class=\"value\">{}</div></div>",
+            escape_html(&label),
+            escape_html(&value)
+        );
+    }
+    html += "</div>\n";
+    html
+}
+
+fn trend_section(total_stats: &TotalStats, merchant_list: &MerchantList) -> String {
+    let (Some(start_time), Some(end_time)) = (total_stats.start_time(), total_stats.end_time())
+    else {
+        return String::new();
+    };
+
+    let start_date = start_time.date();
+    let end_date = end_time.date();
+    let weekly = (end_date - start_date).num_days() > DAILY_TREND_MAX_DAYS;
+
+    //  Bucket start date of each day or week, weeks start on Monday
+    let bucket_of = |date: NaiveDate| {
+        if weekly {
+            date - Duration::days(date.weekday().num_days_from_monday() as i64)
+        } else {
+            date
+        }
+    };
+
+    let mut buckets: IndexMap<NaiveDate, [f64; 4]> = IndexMap::new();
+    let mut date = bucket_of(start_date);
+    while date <= end_date {
+        buckets.insert(date, [0.0; 4]);
+        date += Duration::days(if weekly { 7 } else { 1 });
+    }
+
+    for merchant in merchant_list.merchants().values() {
+        for event in merchant
+            .installing_events()
+            .iter()
+            .chain(merchant.subscription_events().iter())
+        {
+            let Some(time) = event.time() else {
+                continue;
+            };
+            let series_index = match event.category() {
+                Some(EventCategory::Installed) => 0,
+                Some(EventCategory::Uninstalled) => 1,
+                Some(EventCategory::SubscriptionActivated) => 2,
+                Some(EventCategory::SubscriptionCanceled) => 3,
+                _ => continue,
+            };
+            if let Some(bucket) = buckets.get_mut(&bucket_of(time.date())) {
+                bucket[series_index] += 1.0;
+            }
+        }
+    }
+
+    let labels: Vec<String> = buckets
+        .keys()
+        .map(|d| d.format(TREND_LABEL_PATTERN).to_string())
+        .collect();
+    let series: Vec<(&str, Vec<f64>)> = [
+        SERIES_INSTALLED,
+        SERIES_UNINSTALLED,
+        SERIES_NEW_SUB,
+        SERIES_CANCELED_SUB,
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(i, name)| (name, buckets.values().map(|b| b[i]).collect()))
+    .collect();
+
+    format!(
+        "<h2>{}</h2>\n<p>{}</p>\n{}{}",
+        SECTION_TRENDS,
+        if weekly { TREND_WEEKLY } else { TREND_DAILY },
+        legend(&series),
+        line_chart_svg(&labels, &series)
+    )
+}
+
+fn subscription_section(total_stats: &TotalStats) -> String {
+    let details = total_stats.sub_stats_details();
+    let paying_plans = total_stats
+        .paying_sub_stats_details()
+        .new_sub()
+        .monthly_counts();
+
+    let mut html = format!(
+        "<h2>{}</h2>\n<table>\n<tr><th>{}</th><th>{}</th><th>{}</th><th>{}</th><th>{}</th><th>{}</th><th>{}</th><th>{}</th></tr>\n",
+        SECTION_SUBSCRIPTIONS,
+        COL_PLAN,
+        COL_PAYING,
+        COL_NEW_MONTHLY,
+        COL_NEW_YEARLY,
+        COL_CANCELED_MONTHLY,
+        COL_CANCELED_YEARLY,
+        COL_GROWTH_MONTHLY,
+        COL_GROWTH_YEARLY
+    );
+
+    let mut categories: Vec<String> = Vec::new();
+    let mut new_counts: Vec<f64> = Vec::new();
+    let mut canceled_counts: Vec<f64> = Vec::new();
+
+    for plan in details.new_sub().monthly_counts().keys() {
+        let count = |counts: &IndexMap<String, i32>| *counts.get(plan).unwrap_or(&0);
+
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape_html(plan),
+            if paying_plans.contains_key(plan) {
+                YES
+            } else {
+                NO
+            },
+            count(details.new_sub().monthly_counts()),
+            count(details.new_sub().yearly_counts()),
+            count(details.canceled_sub().monthly_counts()),
+            count(details.canceled_sub().yearly_counts()),
+            count(details.sub_growth().monthly_counts()),
+            count(details.sub_growth().yearly_counts())
+        );
+
+        categories.push(plan.clone());
+        new_counts.push(
+            (count(details.new_sub().monthly_counts()) + count(details.new_sub().yearly_counts()))
+                as f64,
+        );
+        canceled_counts.push(
+            (count(details.canceled_sub().monthly_counts())
+                + count(details.canceled_sub().yearly_counts())) as f64,
+        );
+    }
+    html += "</table>\n";
+
+    let series = vec![
+        (SERIES_NEW_SUB, new_counts),
+        (SERIES_CANCELED_SUB, canceled_counts),
+    ];
+    html += &legend(&series);
+    html += &bar_chart_svg(&categories, &series);
+    html += &frozen_cards(total_stats);
+    html
+}
+
+fn frozen_cards(total_stats: &TotalStats) -> String {
+    let cards: [(&str, &u32); 3] = [
+        (CARD_FROZEN_SUB, total_stats.frozen_sub_count()),
+        (CARD_UNFROZEN_SUB, total_stats.unfrozen_sub_count()),
+        (
+            CARD_FROZEN_THEN_CANCELED_SUB,
+            total_stats.frozen_then_canceled_sub_count(),
+        ),
+    ];
+
+    let mut html = String::from("<div class=\"cards\">\n");
+    for (label, value) in cards {
+        let _ = writeln!(
+            html,
+            "<div class=\"card\"><div class=\"label\">{}</div><div class=\"value\">{}</div></div>",
+            escape_html(label),
+            value
+        );
+    }
+    html += "</div>\n";
+    html
+}
+
+fn one_time_section(total_stats: &TotalStats) -> String {
+    let mut html = format!(
+        "<h2>{}</h2>\n<table>\n<tr><th>{}</th><th>{}</th><th>{}</th><th>{}</th><th>{}</th><th>{}</th></tr>\n",
+        SECTION_ONE_TIMES,
+        COL_PACK,
+        COL_PURCHASES,
+        COL_REVENUE,
+        COL_BUYERS,
+        COL_REPEAT_BUYERS,
+        COL_AVERAGE_PACKS_PER_BUYER
+    );
+
+    let rows = std::iter::once((ROW_ALL_PACKS.to_string(), total_stats.one_time_summary())).chain(
+        total_stats
+            .one_time_pack_stats()
+            .iter()
+            .map(|(pack, stats)| (pack.clone(), stats)),
+    );
+    for (pack, stats) in rows {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.2}</td></tr>",
+            escape_html(&pack),
+            stats.purchase_count(),
+            format_money(*stats.revenue()),
+            stats.buyer_count(),
+            stats.repeat_buyer_count(),
+            stats.average_packs_per_buyer()
+        );
+    }
+    html += "</table>\n";
+    html
+}
+
+fn churn_section(total_stats: &TotalStats) -> String {
+    let churn_stats = total_stats.churn_stats();
+
+    let mut html = format!(
+        "<h2>{}</h2>\n<table>\n<tr><th>{}</th><th>{}</th></tr>\n",
+        SECTION_CHURN, COL_FORMULA, COL_RATE
+    );
+    for (formula, rate) in [
+        (ChurnFormula::InstallBased, *total_stats.total_churn_rate()),
+        (ChurnFormula::NetInstallBased, *total_stats.churn_rate()),
+        (ChurnFormula::Logo, *churn_stats.logo_churn_rate()),
+        (ChurnFormula::Revenue, *churn_stats.revenue_churn_rate()),
+    ] {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td></tr>",
+            formula,
+            format_percent(rate)
+        );
+    }
+    html += "</table>\n";
+
+    let _ = writeln!(
+        html,
+        "<table>\n<tr><th></th><th>{}</th><th>{}</th></tr>\n<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n</table>",
+        COL_PERIOD_START,
+        COL_PERIOD_END,
+        ROW_ACTIVE_MERCHANTS,
+        churn_stats.starting_active_count(),
+        churn_stats.ending_active_count(),
+        ROW_SUBSCRIBED_MERCHANTS,
+        churn_stats.starting_sub_count(),
+        churn_stats.ending_sub_count()
+    );
+
+    let _ = writeln!(
+        html,
+        "<table>\n<tr><th></th><th>{}</th></tr>\n<tr><td>{}</td><td>{}</td></tr>\n<tr><td>{}</td><td>{}</td></tr>\n</table>",
+        COL_CHURNED,
+        ROW_CHURNED_UNINSTALLED,
+        churn_stats.churned_uninstalled_count(),
+        ROW_CHURNED_STORE_CLOSED,
+        churn_stats.churned_store_closed_count()
+    );
+
+    let _ = writeln!(
+        html,
+        "<table>\n<tr><th>{}</th><th>{}</th><th>{}</th><th>{}</th></tr>",
+        COL_PLAN, COL_STARTING, COL_CHURNED, COL_RATE
+    );
+    for (plan, plan_churn) in churn_stats.sub_churn() {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape_html(plan),
+            plan_churn.starting_count(),
+            plan_churn.churned_count(),
+            format_percent(*plan_churn.churn_rate())
+        );
+    }
+    html += "</table>\n";
+    html
+}
+
+fn legend(series: &Vec<(&str, Vec<f64>)>) -> String {
+    let mut html = String::from("<div class=\"legend\">");
+    for (i, (name, _)) in series.iter().enumerate() {
+        let _ = write!(
+            html,
+            "<span><i style=\"background:{}\"></i>{}</span>",
+            CHART_COLORS[i % CHART_COLORS.len()],
+            escape_html(name)
+        );
+    }
+    html += "</div>\n";
+    html
+}
+
+/// Y axis grid and labels from 0 to a round maximum, return the maximum
+fn chart_y_axis(svg: &mut String, series: &Vec<(&str, Vec<f64>)>) -> f64 {
+    let max_value = series
+        .iter()
+        .flat_map(|(_, values)| values.iter())
+        .fold(0.0_f64, |max, v| max.max(*v));
+    let step = (max_value / CHART_Y_TICKS as f64).ceil().max(1.0);
+    let y_max = step * CHART_Y_TICKS as f64;
+
+    let plot_height = CHART_HEIGHT - CHART_MARGIN_TOP - CHART_MARGIN_BOTTOM;
+    for tick in 0..=CHART_Y_TICKS {
+        let y =
+            CHART_HEIGHT - CHART_MARGIN_BOTTOM - plot_height * tick as f64 / CHART_Y_TICKS as f64;
+        let _ = write!(
+            svg,
+            "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"#eee\"/><text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+            CHART_MARGIN_LEFT,
+            y,
+            CHART_WIDTH - CHART_MARGIN_RIGHT,
+            y,
+            CHART_MARGIN_LEFT - 4.0,
+            y + 4.0,
+            step * tick as f64
+        );
+    }
+    y_max
+}
+
+fn chart_y(value: f64, y_max: f64) -> f64 {
+    let plot_height = CHART_HEIGHT - CHART_MARGIN_TOP - CHART_MARGIN_BOTTOM;
+    CHART_HEIGHT - CHART_MARGIN_BOTTOM - plot_height * value / y_max
+}
+
+fn line_chart_svg(labels: &[String], series: &Vec<(&str, Vec<f64>)>) -> String {
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+        CHART_WIDTH, CHART_HEIGHT, CHART_WIDTH, CHART_HEIGHT
+    );
+    let y_max = chart_y_axis(&mut svg, series);
+
+    let plot_width = CHART_WIDTH - CHART_MARGIN_LEFT - CHART_MARGIN_RIGHT;
+    let x_of = |index: usize| {
+        if labels.len() > 1 {
+            CHART_MARGIN_LEFT + plot_width * index as f64 / (labels.len() - 1) as f64
+        } else {
+            CHART_MARGIN_LEFT + plot_width / 2.0
+        }
+    };
+
+    let label_step = labels.len().div_ceil(CHART_MAX_X_LABELS).max(1);
+    for (index, label) in labels.iter().enumerate().step_by(label_step) {
+        let _ = write!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+            x_of(index),
+            CHART_HEIGHT - CHART_MARGIN_BOTTOM + 16.0,
+            escape_html(label)
+        );
+    }
+
+    for (i, (_, values)) in series.iter().enumerate() {
+        let points: Vec<String> = values
+            .iter()
+            .enumerate()
+            .map(|(index, v)| format!("{:.1},{:.1}", x_of(index), chart_y(*v, y_max)))
+            .collect();
+        let _ = write!(
+            svg,
+            "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"2\" points=\"{}\"/>",
+            CHART_COLORS[i % CHART_COLORS.len()],
+            points.join(" ")
+        );
+    }
+
+    svg += "</svg>\n";
+    svg
+}
+
+fn bar_chart_svg(categories: &[String], series: &Vec<(&str, Vec<f64>)>) -> String {
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+        CHART_WIDTH, CHART_HEIGHT, CHART_WIDTH, CHART_HEIGHT
+    );
+    let y_max = chart_y_axis(&mut svg, series);
+
+    let plot_width = CHART_WIDTH - CHART_MARGIN_LEFT - CHART_MARGIN_RIGHT;
+    let group_width = plot_width / categories.len().max(1) as f64;
+    let bar_width = group_width * 0.8 / series.len().max(1) as f64;
+
+    for (index, category) in categories.iter().enumerate() {
+        let group_x = CHART_MARGIN_LEFT + group_width * index as f64;
+        let _ = write!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+            group_x + group_width / 2.0,
+            CHART_HEIGHT - CHART_MARGIN_BOTTOM + 16.0,
+            escape_html(category)
+        );
+
+        for (i, (_, values)) in series.iter().enumerate() {
+            let value = values.get(index).copied().unwrap_or(0.0);
+            let y = chart_y(value, y_max);
+            let _ = write!(
+                svg,
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"><title>{}</title></rect>",
+                group_x + group_width * 0.1 + bar_width * i as f64,
+                y,
+                bar_width,
+                CHART_HEIGHT - CHART_MARGIN_BOTTOM - y,
+                CHART_COLORS[i % CHART_COLORS.len()],
+                value
+            );
+        }
+    }
+
+    svg += "</svg>\n";
+    svg
+}
+
+fn period_text(total_stats: &TotalStats) -> String {
+    let format_time = |time: &Option<chrono::NaiveDateTime>| {
+        time.map(|t| t.format(REPORT_TIME_PATTERN).to_string())
+            .unwrap_or(NONE.to_string())
+    };
+    format!(
+        "{} - {}",
+        format_time(total_stats.start_time()),
+        format_time(total_stats.end_time())
+    )
+}
+
+fn format_percent(rate: f64) -> String {
+    format!("{:.2}%", rate)
+}
+
+fn format_money(amount: f64) -> String {
+    format!("{:.2}", amount)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_io::read_pricing_def_from_json_str;
+    use crate::definitions::default_ms_pricing_def::SBM_PRICING_DEF_JSON_STRING;
+
+    fn report_over(days: i64, install_day: i64) -> String {
+        let pricing_defs = read_pricing_def_from_json_str(SBM_PRICING_DEF_JSON_STRING).unwrap();
+        let start_time = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let mut total_stats = TotalStats::new(&pricing_defs);
+        total_stats.set_start_time(Some(start_time));
+        total_stats.set_end_time(Some(start_time + Duration::days(days)));
+        total_stats.build_pretty_time_str();
+
+        let mut installed = AppEvent::new();
+        installed.set_time(Some(start_time + Duration::days(install_day)));
+        installed.set_category(Some(EventCategory::Installed));
+        let mut merchant = Merchant::new(&"shop".to_string(), pricing_defs.one_times());
+        merchant.push_installing_event(&installed);
+        let mut merchant_list = MerchantList::new();
+        merchant_list.update_merchant(merchant);
+
+        render_html_report(&total_stats, &merchant_list)
+    }
+
+    /// Points of the first line of the trend chart, the installed series
+    fn installed_points(html: &str) -> Vec<String> {
+        let points = html.split("points=\"").nth(1).unwrap();
+        points[..points.find('"').unwrap()]
+            .split(' ')
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn html_report_does_not_load_external_resources() {
+        let html = report_over(10, 2);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<style>"));
+        assert!(!html.contains("<link"));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("https://"));
+        assert_eq!(
+            escape_html("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn trend_is_charted_by_day_or_by_week_after_two_months() {
+        let daily = report_over(10, 2);
+        assert!(daily.contains(TREND_DAILY));
+        let points = installed_points(&daily);
+        assert_eq!(points.len(), 11);
+        //  Only the day of the install is above the baseline
+        let baseline = format!("{:.1}", CHART_HEIGHT - CHART_MARGIN_BOTTOM);
+        let raised: Vec<usize> = points
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| !p.ends_with(&format!(",{}", baseline)))
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(raised, [2]);
+
+        //  Jan 1 2025 is a Wednesday, so weeks start on Dec 30 2024
+        let weekly = report_over(100, 2);
+        assert!(weekly.contains(TREND_WEEKLY));
+        assert_eq!(installed_points(&weekly).len(), 15);
+    }
+}
@@ -0,0 +1,349 @@
+use indexmap::{IndexMap, IndexSet};
+use std::fmt::Write;
+
+use crate::definitions::strings::report::*;
+use crate::models::data_model::*;
+
+enum ValueKind {
+    Count,
+    Percent,
+    Money,
+}
+
+/// Render a Markdown summary for chat and wiki posting,
+/// with a comparison column if the stats of a previous run are given
+pub fn render_markdown_summary(
+    total_stats: &TotalStats,
+    previous_stats: Option<&TotalStats>,
+) -> String {
+    let mut md = String::new();
+
+    let _ = writeln!(
+        md,
+        "## {} {} - {}\n",
+        SUMMARY_TITLE,
+        total_stats.start_time_str(),
+        total_stats.end_time_str()
+    );
+    if let Some(previous) = previous_stats {
+        let _ = writeln!(
+            md,
+            "_{} {} - {}_\n",
+            SUMMARY_COMPARED_WITH,
+            previous.start_time_str(),
+            previous.end_time_str()
+        );
+    }
+
+    md += &headline_table(total_stats, previous_stats);
+    md += &plan_table(
+        SECTION_MONTHLY_SUBSCRIPTIONS,
+        total_stats.sub_stats_details(),
+        previous_stats.map(|p| p.sub_stats_details()),
+        |counter| counter.monthly_counts(),
+    );
+    md += &plan_table(
+        SECTION_YEARLY_SUBSCRIPTIONS,
+        total_stats.sub_stats_details(),
+        previous_stats.map(|p| p.sub_stats_details()),
+        |counter| counter.yearly_counts(),
+    );
+    md += &one_time_table(total_stats, previous_stats);
+
+    md
+}
+
+fn headline_metrics(total_stats: &TotalStats) -> Vec<(String, f64, ValueKind)> {
+    vec![
+        (
+            CARD_INSTALLED.to_string(),
+            *total_stats.installed_count() as f64,
+            ValueKind::Count,
+        ),
+        (
+            CARD_UNINSTALLED.to_string(),
+            *total_stats.uninstalled_count() as f64,
+            ValueKind::Count,
+        ),
+        (
+            CARD_MERCHANT_GROWTH.to_string(),
+            *total_stats.merchant_growth() as f64,
+            ValueKind::Count,
+        ),
+        (
+            CARD_STORE_CLOSURE_GROWTH.to_string(),
+            *total_stats.store_closure_growth() as f64,
+            ValueKind::Count,
+        ),
+        (
+            CARD_NEW_SUB.to_string(),
+            *total_stats.new_sub_count() as f64,
+            ValueKind::Count,
+        ),
+        (
+            CARD_CANCELED_SUB.to_string(),
+            *total_stats.canceled_sub_count() as f64,
+            ValueKind::Count,
+        ),
+        (
+            CARD_NEW_PAYING_SUB.to_string(),
+            *total_stats.new_paying_sub_count() as f64,
+            ValueKind::Count,
+        ),
+        (
+            CARD_CANCELED_PAYING_SUB.to_string(),
+            *total_stats.canceled_paying_sub_count() as f64,
+            ValueKind::Count,
+        ),
+        (
+            CARD_PAID_GROWTH.to_string(),
+            *total_stats.paid_growth() as f64,
+            ValueKind::Count,
+        ),
+        (
+            CARD_ONE_TIME.to_string(),
+            *total_stats.one_time_count() as f64,
+            ValueKind::Count,
+        ),
+        (
+            format!(
+                "{} ({})",
+                CARD_HEADLINE_CHURN,
+                total_stats.headline_churn_formula()
+            ),
+            *total_stats.headline_churn_rate(),
+            ValueKind::Percent,
+        ),
+        (
+            CARD_REVENUE.to_string(),
+            *total_stats.revenue_stats().total_revenue(),
+            ValueKind::Money,
+        ),
+    ]
+}
+
+fn headline_table(total_stats: &TotalStats, previous_stats: Option<&TotalStats>) -> String {
+    let mut md = format!("### {}\n\n", SECTION_HEADLINE);
+
+    let Some(previous) = previous_stats else {
+        let _ = writeln!(md, "| {} | {} |\n|---|---:|", COL_METRIC, COL_VALUE);
+        for (label, value, kind) in headline_metrics(total_stats) {
+            let _ = writeln!(
+                md,
+                "| {} | {} |",
+                escape_markdown(&label),
+                format_value(value, &kind)
+            );
+        }
+        md += "\n";
+        return md;
+    };
+
+    let _ = writeln!(
+        md,
+        "| {} | {} | {} | {} |\n|---|---:|---:|---:|",
+        COL_METRIC, COL_CURRENT, COL_PREVIOUS, COL_CHANGE
+    );
+    for ((label, value, kind), (_, previous_value, _)) in headline_metrics(total_stats)
+        .into_iter()
+        .zip(headline_metrics(previous))
+    {
+        let _ = writeln!(
+            md,
+            "| {} | {} | {} | {} |",
+            escape_markdown(&label),
+            format_value(value, &kind),
+            format_value(previous_value, &kind),
+            format_change(&MetricChange::new(previous_value, value), &kind)
+        );
+    }
+    md += "\n";
+    md
+}
+
+fn plan_table(
+    title: &str,
+    details: &DetailedSubscriptionStats,
+    previous_details: Option<&DetailedSubscriptionStats>,
+    counts_of: fn(&SubscriptionStatsCounter) -> &IndexMap<String, i32>,
+) -> String {
+    let count = |counter: &SubscriptionStatsCounter, plan: &str| {
+        *counts_of(counter).get(plan).unwrap_or(&0) as f64
+    };
+
+    //  Plans of both runs, a plan may be added or removed in between
+    let mut plans: IndexSet<&String> = counts_of(details.new_sub()).keys().collect();
+    if let Some(previous) = previous_details {
+        plans.extend(counts_of(previous.new_sub()).keys());
+    }
+
+    let mut md = format!(
+        "### {}\n\n| {} | {} | {} | {} |",
+        title, COL_PLAN, COL_NEW, COL_CANCELED, COL_GROWTH
+    );
+    if previous_details.is_some() {
+        let _ = writeln!(
+            md,
+            " {} |\n|---|---:|---:|---:|---:|",
+            COL_GROWTH_VS_PREVIOUS
+        );
+    } else {
+        md += "\n|---|---:|---:|---:|\n";
+    }
+
+    for plan in plans {
+        let _ = write!(
+            md,
+            "| {} | {} | {} | {} |",
+            escape_markdown(plan),
+            count(details.new_sub(), plan),
+            count(details.canceled_sub(), plan),
+            count(details.sub_growth(), plan)
+        );
+        if let Some(previous) = previous_details {
+            let _ = write!(
+                md,
+                " {} |",
+                format_change(
+                    &MetricChange::new(
+                        count(previous.sub_growth(), plan),
+                        count(details.sub_growth(), plan)
+                    ),
+                    &ValueKind::Count
+                )
+            );
+        }
+        md += "\n";
+    }
+    md += "\n";
+    md
+}
+
+fn one_time_table(total_stats: &TotalStats, previous_stats: Option<&TotalStats>) -> String {
+    let mut md = format!(
+        "### {}\n\n| {} | {} | {} |",
+        SECTION_ONE_TIMES, COL_PACK, COL_PURCHASES, COL_REVENUE
+    );
+    if previous_stats.is_some() {
+        let _ = writeln!(md, " {} |\n|---|---:|---:|---:|", COL_PURCHASES_VS_PREVIOUS);
+    } else {
+        md += "\n|---|---:|---:|\n";
+    }
+
+    let purchase_count = |stats: &TotalStats, pack: Option<&str>| match pack {
+        None => *stats.one_time_summary().purchase_count() as f64,
+        Some(p) => stats
+            .one_time_pack_stats()
+            .get(p)
+            .map(|s| *s.purchase_count() as f64)
+            .unwrap_or(0.0),
+    };
+
+    let rows = std::iter::once((None, total_stats.one_time_summary())).chain(
+        total_stats
+            .one_time_pack_stats()
+            .iter()
+            .map(|(pack, stats)| (Some(pack.as_str()), stats)),
+    );
+    for (pack, stats) in rows {
+        let _ = write!(
+            md,
+            "| {} | {} | {} |",
+            escape_markdown(pack.unwrap_or(ROW_ALL_PACKS)),
+            stats.purchase_count(),
+            format_value(*stats.revenue(), &ValueKind::Money)
+        );
+        if let Some(previous) = previous_stats {
+            let _ = write!(
+                md,
+                " {} |",
+                format_change(
+                    &MetricChange::new(
+                        purchase_count(previous, pack),
+                        purchase_count(total_stats, pack)
+                    ),
+                    &ValueKind::Count
+                )
+            );
+        }
+        md += "\n";
+    }
+    md
+}
+
+fn format_value(value: f64, kind: &ValueKind) -> String {
+    match kind {
+        ValueKind::Count => format!("{}", value),
+        ValueKind::Percent => format!("{:.2}%", value),
+        ValueKind::Money => format!("{:.2}", value),
+    }
+}
+
+/// Signed change, with percent change if the previous value is not zero
+fn format_change(change: &MetricChange, kind: &ValueKind) -> String {
+    let amount = match kind {
+        ValueKind::Count => format!("{:+}", change.change()),
+        ValueKind::Percent => format!("{:+.2} pp", change.change()),
+        ValueKind::Money => format!("{:+.2}", change.change()),
+    };
+
+    match (kind, change.percent_change()) {
+        (ValueKind::Percent, _) | (_, None) => amount,
+        (_, Some(percent)) => format!("{} ({:+.1}%)", amount, percent),
+    }
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_io::read_pricing_def_from_json_str;
+    use crate::definitions::default_ms_pricing_def::SBM_PRICING_DEF_JSON_STRING;
+
+    fn stats(installed_count: u32, total_churn_rate: f64) -> TotalStats {
+        let pricing_defs = read_pricing_def_from_json_str(SBM_PRICING_DEF_JSON_STRING).unwrap();
+        let mut total_stats = TotalStats::new(&pricing_defs);
+        total_stats.increase_installed_count(installed_count);
+        total_stats.set_total_churn_rate(total_churn_rate);
+        total_stats.select_headline_churn(&ChurnFormula::InstallBased);
+        total_stats
+    }
+
+    fn row<'a>(md: &'a str, label: &str) -> &'a str {
+        md.lines()
+            .find(|l| l.starts_with(&format!("| {} |", label)))
+            .unwrap()
+    }
+
+    #[test]
+    fn markdown_summary_adds_change_columns_only_with_previous_stats() {
+        let current = stats(12, 7.5);
+        let churn_label = format!("{} ({})", CARD_HEADLINE_CHURN, ChurnFormula::InstallBased);
+
+        let md = render_markdown_summary(&current, None);
+        assert!(!md.contains(SUMMARY_COMPARED_WITH));
+        assert_eq!(
+            row(&md, CARD_INSTALLED),
+            format!("| {} | 12 |", CARD_INSTALLED)
+        );
+        assert_eq!(
+            row(&md, &churn_label),
+            format!("| {} | 7.50% |", churn_label)
+        );
+
+        let md = render_markdown_summary(&current, Some(&stats(10, 5.0)));
+        assert!(md.contains(SUMMARY_COMPARED_WITH));
+        assert_eq!(
+            row(&md, CARD_INSTALLED),
+            format!("| {} | 12 | 10 | +2 (+20.0%) |", CARD_INSTALLED)
+        );
+        //  Rates change by percentage points
+        assert_eq!(
+            row(&md, &churn_label),
+            format!("| {} | 7.50% | 5.00% | +2.50 pp |", churn_label)
+        );
+    }
+}
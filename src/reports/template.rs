@@ -0,0 +1,94 @@
+use minijinja::{Environment, Value, context};
+
+use crate::models::data_model::*;
+
+/// Render a user-provided template with the total stats and merchant data as context.
+/// The template name decides auto-escaping, e.g. a name ending in `.html` is HTML-escaped
+pub fn render_template_report(
+    template_name: &str,
+    template_source: &str,
+    total_stats: &TotalStats,
+    merchant_data_list: &MerchantList,
+) -> anyhow::Result<String> {
+    let mut env = Environment::new();
+    env.add_template(template_name, template_source)?;
+
+    let rendered = env.get_template(template_name)?.render(context! {
+        total_stats => Value::from_serialize(total_stats),
+        merchant_data => Value::from_serialize(merchant_data_list),
+    })?;
+
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_io::read_pricing_def_from_json_str;
+    use crate::definitions::default_ms_pricing_def::SBM_PRICING_DEF_JSON_STRING;
+    use crate::definitions::default_report_templates::*;
+
+    fn report_data() -> (TotalStats, MerchantList) {
+        let pricing_defs = read_pricing_def_from_json_str(SBM_PRICING_DEF_JSON_STRING).unwrap();
+        let mut total_stats = TotalStats::new(&pricing_defs);
+        total_stats.increase_installed_count(7);
+
+        let mut merchant_list = MerchantList::new();
+        let mut merchant = Merchant::new(
+            &"<b>shop</b>.myshopify.com".to_string(),
+            pricing_defs.one_times(),
+        );
+        merchant.set_lifetime_revenue(12.5);
+        merchant_list.update_merchant(merchant);
+
+        (total_stats, merchant_list)
+    }
+
+    #[test]
+    fn bundled_templates_render_stats_and_merchants() {
+        let (total_stats, merchant_list) = report_data();
+
+        let summary = render_template_report(
+            "summary.txt",
+            SUMMARY_TXT_TEMPLATE_STRING,
+            &total_stats,
+            &merchant_list,
+        )
+        .unwrap();
+        assert!(summary.contains("Installs: 7\n"));
+
+        let merchants = render_template_report(
+            "merchants.html",
+            MERCHANTS_HTML_TEMPLATE_STRING,
+            &total_stats,
+            &merchant_list,
+        )
+        .unwrap();
+        assert!(merchants.contains("<td>&lt;b&gt;shop&lt;&#x2f;b&gt;.myshopify.com</td>"));
+        assert!(merchants.contains("<td>12.5</td>"));
+    }
+
+    #[test]
+    fn text_templates_are_not_escaped() {
+        let (total_stats, merchant_list) = report_data();
+
+        let rendered = render_template_report(
+            "domains.txt",
+            "{% for d, m in merchant_data.merchants|items %}{{ d }}{% endfor %}",
+            &total_stats,
+            &merchant_list,
+        )
+        .unwrap();
+        assert_eq!(rendered, "<b>shop</b>.myshopify.com");
+    }
+
+    #[test]
+    fn invalid_template_is_an_error() {
+        let (total_stats, merchant_list) = report_data();
+
+        assert!(
+            render_template_report("broken.txt", "{% for x in %}", &total_stats, &merchant_list)
+                .is_err()
+        );
+    }
+}